@@ -0,0 +1,146 @@
+use image::Rgb;
+use strum::{EnumCount, FromRepr};
+
+use crate::board::Board;
+
+/// What the colour of a cell shows
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum Colouring {
+    // Only whether the cell is alive
+    Plain,
+    // How long a live cell has been alive
+    Age,
+    // The age of live cells, and how recently dead cells died
+    Trails,
+}
+
+impl Colouring {
+    pub fn parse(name: &str) -> Result<Colouring, String> {
+        (0..Colouring::COUNT)
+            .filter_map(Colouring::from_repr)
+            .find(|colouring| colouring.key() == name.trim().to_lowercase())
+            .ok_or(format!("Unknown colouring '{}', expected one of plain, age or trails", name))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Colouring::Plain => "plain",
+            Colouring::Age => "age",
+            Colouring::Trails => "trails",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Colouring::Plain => "Plain",
+            Colouring::Age => "Age",
+            Colouring::Trails => "Age and trails",
+        }
+    }
+
+    pub fn next(&self) -> Colouring {
+        Colouring::from_repr((*self as usize + 1) % Colouring::COUNT).unwrap()
+    }
+}
+
+/// The colour ramps of the age and trail colourings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AgeColours {
+    pub colouring: Colouring,
+    // Live cells go from the newborn to the old colour over this many generations
+    pub max_age: u32,
+    // Dead cells fade from the trail colour to the background over this many generations
+    pub trail_length: u32,
+    pub newborn: Rgb<u8>,
+    pub old: Rgb<u8>,
+    pub trail: Rgb<u8>,
+}
+
+impl Default for AgeColours {
+    fn default() -> Self {
+        AgeColours {
+            colouring: Colouring::Plain,
+            max_age: 100,
+            trail_length: 16,
+            newborn: Rgb([140, 224, 255]),
+            old: Rgb([19, 76, 207]),
+            trail: Rgb([122, 31, 61]),
+        }
+    }
+}
+
+/// Tracks how many generations each cell of a board has been alive, and how many generations ago
+/// each dead cell died. Like the cycle detector it expects to observe the board one generation
+/// after the other, any other jump starts over with every live cell newborn and no trails.
+#[derive(Default)]
+pub struct CellAges {
+    width: usize,
+    height: usize,
+    // Generations alive for live cells, minus the generations since dying for dead cells and 0
+    // for cells that haven't been alive since the ages were reset
+    ages: Vec<i16>,
+    last_generation: Option<i128>,
+}
+
+impl CellAges {
+    pub fn new() -> Self {
+        CellAges::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.ages.clear();
+        self.last_generation = None;
+    }
+
+    /// Ages the cells when the board is the next generation, and picks up cells that were drawn or
+    /// erased when it is the same generation
+    pub fn observe(&mut self, board: &Board) {
+        let size_changed = (board.width(), board.height()) != (self.width, self.height);
+        let step = match self.last_generation {
+            _ if size_changed || self.ages.is_empty() => None,
+            Some(last) if board.generation() == last + 1 => Some(true),
+            Some(last) if board.generation() == last => Some(false),
+            _ => None,
+        };
+
+        match step {
+            Some(next) => {
+                for y in 0..board.height() {
+                    for x in 0..board.width() {
+                        let age = &mut self.ages[y * board.width() + x];
+                        *age = match (board.is_cell_occupied(x, y), *age) {
+                            (true, age) if age > 0 && next => age.saturating_add(1),
+                            (true, age) if age > 0 => age,
+                            (true, _) => 1,
+                            (false, age) if age > 0 => -1,
+                            (false, age) if age < 0 && next => age.saturating_sub(1),
+                            (false, age) => age,
+                        };
+                    }
+                }
+            }
+            None => {
+                self.width = board.width();
+                self.height = board.height();
+                self.ages = vec![0; board.width() * board.height()];
+                for (x, y) in board.live_cells() {
+                    self.ages[y * board.width() + x] = 1;
+                }
+            }
+        }
+        self.last_generation = Some(board.generation());
+    }
+
+    /// Generations the cell has been alive, 1 for a newborn cell and 0 for a dead cell
+    pub fn age(&self, x: usize, y: usize) -> u32 {
+        self.ages.get(y * self.width + x).map_or(0, |&age| age.max(0) as u32)
+    }
+
+    /// Generations since the cell died, if it was alive since the ages started
+    pub fn dead_for(&self, x: usize, y: usize) -> Option<u32> {
+        match self.ages.get(y * self.width + x) {
+            Some(&age) if age < 0 => Some(age.unsigned_abs() as u32),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use crate::cycle::Cycle;
+
+/// When a running simulation stops by itself, every condition is off by default
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AutoPause {
+    pub on_extinction: bool,
+    // Stop once the pattern repeats with at most this period, still lifes have period 1
+    pub max_period: Option<i128>,
+    pub at_generation: Option<i128>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Extinct { generation: i128 },
+    Cycle { generation: i128, cycle: Cycle },
+    TargetGeneration { generation: i128 },
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StopReason::Extinct { generation } =>
+                write!(f, "died out at generation {}", generation),
+            StopReason::Cycle { generation, cycle } =>
+                write!(f, "settled at generation {}, {}", generation, cycle.to_string().to_lowercase()),
+            StopReason::TargetGeneration { generation } =>
+                write!(f, "reached generation {}", generation),
+        }
+    }
+}
+
+impl AutoPause {
+    /// Returns why the simulation should stop at the given state, if it should
+    pub fn check(&self, generation: i128, population: i128, cycle: Option<Cycle>) -> Option<StopReason> {
+        if self.on_extinction && population == 0 {
+            return Some(StopReason::Extinct { generation });
+        }
+        if self.at_generation.is_some_and(|target| generation >= target) {
+            return Some(StopReason::TargetGeneration { generation });
+        }
+
+        let cycle = cycle?;
+        let period = match cycle {
+            Cycle::Extinct { .. } => return None,
+            Cycle::StillLife { .. } => 1,
+            Cycle::Oscillator { period, .. } | Cycle::Spaceship { period, .. } => period,
+        };
+        match self.max_period {
+            Some(max_period) if period <= max_period => Some(StopReason::Cycle { generation, cycle }),
+            _ => None,
+        }
+    }
+}
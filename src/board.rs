@@ -1,35 +1,44 @@
 use crate::direction::Direction;
 use crate::pattern::Pattern;
+use crate::simulation::BoundingBox;
+use crate::topology::Topology;
 
-pub const BOARD_SIZE: usize = 128;
-
-const NEIGHBOUR_CELLS: [[i32; 2]; 8] = [
-    [0, -1],
-    [1, -1],
-    [1, 0],
-    [1, 1],
-    [0, 1],
-    [-1, 1],
-    [-1, 0],
-    [-1, -1]
-];
+pub const DEFAULT_BOARD_SIZE: usize = 128;
 
+// Cells are stored as rows of bits, each row padded to a whole number of 64 bit words. The bit
+// for column x of a row is bit x % 64 of word x / 64, unused bits of the last word are always 0.
+#[derive(Clone)]
 pub struct Board {
-    pub generation: i128,
-    pub cells: [[bool; BOARD_SIZE]; BOARD_SIZE],
-    pub population: i128,
+    generation: i128,
+    width: usize,
+    height: usize,
+    population: i128,
+    pub topology: Topology,
+    cells: Vec<u64>,
+    words_per_row: usize,
 }
 
 impl Board {
-    pub fn new() -> Self {
+    pub fn with_size(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
         Board {
             generation: 1,
-            cells: [[false; BOARD_SIZE]; BOARD_SIZE],
+            width,
+            height,
             population: 0,
+            topology: Topology::Torus,
+            cells: vec![0; words_per_row * height],
+            words_per_row,
         }
     }
 
-    #[allow(dead_code)]
+    /// Creates an empty board with the same size and topology as this board
+    pub fn cleared(&self) -> Self {
+        let mut board = Board::with_size(self.width, self.height);
+        board.topology = self.topology;
+        board
+    }
+
     pub fn place_pattern(&mut self, pattern: Pattern, x: usize, y: usize) {
         let pattern_grid = pattern.grid();
         self.set_cells(pattern_grid, x, y);
@@ -41,73 +50,138 @@ impl Board {
     }
 
     pub fn set_cells(&mut self, cells: Vec<Vec<u8>>, x: usize, y: usize) {
-        let y = y - (cells.len() / 2);
-        let x = x - (cells.get(0).unwrap().len() / 2);
+        if cells.is_empty() {
+            return;
+        }
+
+        let y = y as i64 - (cells.len() / 2) as i64;
+        let x = x as i64 - (cells.get(0).unwrap().len() / 2) as i64;
 
         for (pattern_y, row) in cells.iter().enumerate() {
             for (pattern_x, &cell) in row.iter().enumerate() {
-                let board_x = pattern_x + x;
-                let board_y = pattern_y + y;
+                let board_x = pattern_x as i64 + x;
+                let board_y = pattern_y as i64 + y;
 
-                if board_x < BOARD_SIZE && board_y < BOARD_SIZE {
+                if let Some((board_x, board_y)) = self.resolve(board_x, board_y) {
                     self.set_cell(board_x, board_y, cell == 1);
                 }
             }
         }
     }
 
-    pub fn set_cell(&mut self, x: usize, y: usize, value: bool) {
-        if x >= BOARD_SIZE || y >= BOARD_SIZE {
-            return;
-        }
+    pub fn generation(&self) -> i128 {
+        self.generation
+    }
 
-        if value && !self.cells[y][x] {
-            self.population += 1;
-        } else if !value && self.cells[y][x] {
-            self.population -= 1;
-        }
+    pub(crate) fn set_generation(&mut self, generation: i128) {
+        self.generation = generation;
+    }
 
-        self.cells[y][x] = value;
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    pub fn is_cell_occupied(&self, x: usize, y: usize) -> bool {
-        if y >= BOARD_SIZE || x >= BOARD_SIZE {
-            panic!("Index out of bounds");
-        }
-        self.cells[y][x]
+    pub fn height(&self) -> usize {
+        self.height
     }
 
-    pub fn get_active_neighbours(&self, x: i32, y: i32) -> i8 {
-        if y >= BOARD_SIZE as i32 || x >= BOARD_SIZE as i32 || y < 0 || x < 0 {
-            panic!("Index out of bounds");
-        }
+    /// The number of alive cells, kept up to date as cells are set
+    pub fn population(&self) -> i128 {
+        self.population
+    }
 
-        let mut active: i8 = 0;
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
 
-        for neighbour in NEIGHBOUR_CELLS {
-            let mut x = x + neighbour[0];
-            let mut y = y + neighbour[1];
+    /// The packed bits of a single row of the board
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.cells[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
 
-            // Wrap around the board
-            if x >= BOARD_SIZE as i32 {
-                x -= BOARD_SIZE as i32;
-            }
-            if x < 0 {
-                x += BOARD_SIZE as i32;
-            }
+    /// Replaces all cells with packed rows as returned by `row`, updating the population
+    pub(crate) fn set_rows(&mut self, cells: Vec<u64>) {
+        assert_eq!(cells.len(), self.words_per_row * self.height, "Invalid number of words");
+        self.population = cells.iter().map(|word| word.count_ones() as i128).sum();
+        self.cells = cells;
+    }
 
-            if y >= BOARD_SIZE as i32 {
-                y -= BOARD_SIZE as i32;
-            }
-            if y < 0 {
-                y += BOARD_SIZE as i32;
-            }
+    pub fn live_cells(&self) -> impl Iterator<Item=(usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().flat_map(move |(index, &word)| {
+                (0..64).filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| (index * 64 + bit, y))
+            })
+        })
+    }
 
-            if self.is_cell_occupied(x as usize, y as usize) {
-                active += 1;
+    /// Returns the smallest rectangle that contains all active cells of the board
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let rows: Vec<usize> = (0..self.height)
+            .filter(|&y| self.row(y).iter().any(|&word| word != 0))
+            .collect();
+        let (&top, &bottom) = (rows.first()?, rows.last()?);
+
+        let mut columns = vec![0u64; self.words_per_row];
+        for &y in &rows {
+            for (column, &word) in columns.iter_mut().zip(self.row(y)) {
+                *column |= word;
             }
         }
 
-        return active;
+        let first = columns.iter().position(|&word| word != 0)?;
+        let last = columns.iter().rposition(|&word| word != 0)?;
+        let left = first * 64 + columns[first].trailing_zeros() as usize;
+        let right = last * 64 + 63 - columns[last].leading_zeros() as usize;
+
+        Some(BoundingBox {
+            x: left as i64,
+            y: top as i64,
+            width: (right - left + 1) as u64,
+            height: (bottom - top + 1) as u64,
+        })
+    }
+
+    /// Returns the smallest grid that contains all active cells of the board
+    pub fn to_grid(&self) -> Vec<Vec<u8>> {
+        let Some(bounds) = self.bounding_box() else {
+            return Vec::new();
+        };
+        let (left, top) = (bounds.x as usize, bounds.y as usize);
+        let (right, bottom) = (left + bounds.width as usize, top + bounds.height as usize);
+
+        (top..bottom)
+            .map(|y| (left..right).map(|x| self.is_cell_occupied(x, y) as u8).collect())
+            .collect()
+    }
+
+    pub fn set_cell(&mut self, x: usize, y: usize, value: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = y * self.words_per_row + x / 64;
+        let mask = 1u64 << (x % 64);
+        let active = self.cells[index] & mask != 0;
+
+        if value && !active {
+            self.population += 1;
+            self.cells[index] |= mask;
+        } else if !value && active {
+            self.population -= 1;
+            self.cells[index] &= !mask;
+        }
+    }
+
+    pub fn is_cell_occupied(&self, x: usize, y: usize) -> bool {
+        if y >= self.height || x >= self.width {
+            panic!("Index out of bounds");
+        }
+        self.cells[y * self.words_per_row + x / 64] & (1u64 << (x % 64)) != 0
+    }
+
+    /// Maps a coordinate onto a cell of the board according to the board's topology
+    pub fn resolve(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        self.topology.resolve(x, y, self.width, self.height)
     }
 }
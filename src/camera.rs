@@ -0,0 +1,61 @@
+// Zoom levels in pixels per cell
+const MIN_ZOOM: f64 = 0.125;
+const MAX_ZOOM: f64 = 128.0;
+// Zoom factor of one line of the scroll wheel, touchpads scroll in pixels
+const ZOOM_STEP: f64 = 1.1;
+const PIXELS_PER_LINE: f64 = 20.0;
+
+/// The part of the board shown in the window, positions on screen are in physical pixels with
+/// the origin at the top left of the window
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    // Board position shown at the top left of the window, in cells
+    pub offset: (f64, f64),
+    // Size of a cell in pixels
+    pub zoom: f64,
+}
+
+impl Camera {
+    /// Shows the whole board as large as fits in the window, centered
+    pub fn fit(window_size: (f64, f64), board_size: (usize, usize)) -> Self {
+        let (board_width, board_height) = (board_size.0 as f64, board_size.1 as f64);
+        let zoom = (window_size.0 / board_width).min(window_size.1 / board_height).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        Camera {
+            offset: (
+                (board_width - window_size.0 / zoom) / 2.0,
+                (board_height - window_size.1 / zoom) / 2.0,
+            ),
+            zoom,
+        }
+    }
+
+    /// The board position under a position on screen, in fractional cells
+    pub fn screen_to_board(&self, position: (f64, f64)) -> (f64, f64) {
+        (self.offset.0 + position.0 / self.zoom, self.offset.1 + position.1 / self.zoom)
+    }
+
+    /// Moves the board along with the cursor, by a distance in pixels
+    pub fn pan(&mut self, distance: (f64, f64)) {
+        self.offset.0 -= distance.0 / self.zoom;
+        self.offset.1 -= distance.1 / self.zoom;
+    }
+
+    /// Zooms in for positive and out for negative lines of the scroll wheel, keeping the cell under
+    /// the cursor in place
+    pub fn zoom_at(&mut self, position: (f64, f64), lines: f64) {
+        let anchor = self.screen_to_board(position);
+        self.zoom = (self.zoom * ZOOM_STEP.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset = (anchor.0 - position.0 / self.zoom, anchor.1 - position.1 / self.zoom);
+    }
+
+    pub fn zoom_at_pixels(&mut self, position: (f64, f64), pixels: f64) {
+        self.zoom_at(position, pixels / PIXELS_PER_LINE);
+    }
+
+    /// Keeps the cell in the center of the window in the center when its size changes
+    pub fn resize(&mut self, old_size: (f64, f64), new_size: (f64, f64)) {
+        self.offset.0 += (old_size.0 - new_size.0) / 2.0 / self.zoom;
+        self.offset.1 += (old_size.1 - new_size.1) / 2.0 / self.zoom;
+    }
+}
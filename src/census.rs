@@ -0,0 +1,379 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::board::Board;
+use crate::pattern::Pattern;
+use crate::plaintext;
+use crate::rle;
+use crate::rule::Rule;
+use crate::simulation::{perform_generation, Simulation};
+use crate::sparse::SparseBoard;
+
+// Common objects of Conway's Life with their period, the ones that don't keep their shape under
+// the rule of a census are left out
+const KNOWN_OBJECTS: [(&str, &str, i128); 17] = [
+    ("block", "OO\nOO", 1),
+    ("beehive", ".OO.\nO..O\n.OO.", 1),
+    ("loaf", ".OO.\nO..O\n.O.O\n..O.", 1),
+    ("boat", "OO.\nO.O\n.O.", 1),
+    ("ship", "OO.\nO.O\n.OO", 1),
+    ("tub", ".O.\nO.O\n.O.", 1),
+    ("pond", ".OO.\nO..O\nO..O\n.OO.", 1),
+    ("long boat", "OO..\nO.O.\n.O.O\n..O.", 1),
+    ("barge", ".O..\nO.O.\n.O.O\n..O.", 1),
+    ("mango", ".OO..\nO..O.\n.O..O\n..OO.", 1),
+    ("aircraft carrier", "OO..\nO..O\n..OO", 1),
+    ("snake", "OO.O\nO.OO", 1),
+    ("eater 1", "OO..\nO.O.\n..O.\n..OO", 1),
+    ("blinker", "OOO", 2),
+    ("lightweight spaceship", ".O..O\nO....\nO...O\nOOOO.", 4),
+    ("middleweight spaceship", "...O..\n.O...O\nO.....\nO....O\nOOOOO.", 4),
+    ("heavyweight spaceship", "...OO..\n.O....O\nO......\nO.....O\nOOOOOO.", 4),
+];
+
+// The objects the viewer places itself, taken from their pattern grids
+const KNOWN_PATTERNS: [(&str, Pattern, i128); 3] = [
+    ("glider", Pattern::Glider, 4),
+    ("beacon", Pattern::Beacon, 2),
+    ("toad", Pattern::Toad, 2),
+];
+
+// Maps a cell onto one of the eight rotations and reflections of a shape
+type Orientation = fn((i64, i64)) -> (i64, i64);
+
+/// The canonical form of every phase of the known objects under a rule, mapped to their name.
+/// Stepping the objects takes a while, so a census of many boards should build them only once.
+pub struct KnownObjects {
+    rule: Rule,
+    phases: HashMap<Vec<(i64, i64)>, &'static str>,
+}
+
+impl KnownObjects {
+    // Steps each object through its period on an unbounded board, objects that don't come back to
+    // their first phase are left out
+    pub fn new(rule: &Rule) -> KnownObjects {
+        let objects = KNOWN_OBJECTS.iter()
+            .map(|&(name, cells, period)| (name, plaintext::parse(cells).unwrap().grid, period))
+            .chain(KNOWN_PATTERNS.iter().map(|(name, pattern, period)| (*name, pattern.grid(), *period)));
+
+        let mut phases = HashMap::new();
+        for (name, grid, period) in objects {
+            let mut simulation = match SparseBoard::new(*rule) {
+                Ok(simulation) => simulation,
+                Err(_) => break,
+            };
+            simulation.set_cells(&grid, 0, 0);
+
+            let mut object_phases = Vec::new();
+            for _ in 0..period {
+                object_phases.push(canonical_form(&simulation.live_cells()));
+                simulation.perform_generation();
+            }
+            if canonical_form(&simulation.live_cells()) == object_phases[0] {
+                for phase in object_phases {
+                    phases.entry(phase).or_insert(name);
+                }
+            }
+        }
+
+        KnownObjects { rule: *rule, phases }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CensusEntry {
+    pub name: Option<&'static str>,
+    // Run length encoded cells of the object in its canonical orientation, like 2o$2o for a block
+    pub code: String,
+    pub population: usize,
+    pub count: usize,
+}
+
+/// The objects a board consists of, counted by their shape regardless of position and orientation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+}
+
+impl fmt::Display for Census {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            match entry.name {
+                Some(name) => writeln!(f, "{} {}", entry.count, name)?,
+                None => writeln!(f, "{} unnamed {}-cell object {}", entry.count, entry.population, entry.code)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Census {
+    /// Splits the live cells of a board into objects of touching cells and identifies them. Cells
+    /// that touch in the board or in its next generation belong to the same object, which keeps
+    /// oscillators like the beacon together in the phase where their parts don't touch.
+    pub fn of_board(board: &Board, known: &KnownObjects) -> Census {
+        let next = perform_generation(board, &known.rule, 1);
+        let current: HashSet<(usize, usize)> = board.live_cells().collect();
+        let touching = current.iter().copied().chain(next.live_cells()).collect();
+
+        let objects = clusters(&current, touching, |(x, y)| (x as i64, y as i64), |x, y| board.resolve(x, y));
+        Census::of_objects(merge_known(objects, &known.phases), &known.phases)
+    }
+
+    /// Takes the census of cells on an unbounded plane, like the live cells of a `SparseBoard`
+    pub fn of_cells(cells: &[(i64, i64)], known: &KnownObjects) -> Census {
+        let current: HashSet<(i64, i64)> = cells.iter().copied().collect();
+        let mut touching = current.clone();
+        if let Ok(mut simulation) = SparseBoard::new(known.rule) {
+            for &(x, y) in cells {
+                simulation.set_cell(x, y, true);
+            }
+            simulation.perform_generation();
+            touching.extend(simulation.live_cells());
+        }
+
+        let objects = clusters(&current, touching, |cell| cell, |x, y| Some((x, y)));
+        Census::of_objects(merge_known(objects, &known.phases), &known.phases)
+    }
+
+    fn of_objects(objects: Vec<Vec<(i64, i64)>>, known: &HashMap<Vec<(i64, i64)>, &'static str>) -> Census {
+        // Phases of the same known object are counted together, unknown objects by their shape
+        let mut entries: HashMap<String, CensusEntry> = HashMap::new();
+        for cells in objects {
+            let cells = canonical_form(&cells);
+            let name = known.get(&cells).copied();
+            let code = encode(&cells);
+            let entry = entries.entry(name.map_or(code.clone(), |name| name.to_string()))
+                .or_insert_with(|| CensusEntry { name, code: code.clone(), population: cells.len(), count: 0 });
+
+            entry.count += 1;
+            if code < entry.code {
+                entry.code = code;
+                entry.population = cells.len();
+            }
+        }
+
+        let mut entries: Vec<CensusEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count)
+            .then(a.population.cmp(&b.population))
+            .then(a.code.cmp(&b.code)));
+
+        Census { entries }
+    }
+}
+
+// Splits the touching cells into connected clusters and returns the current cells of each one.
+// Positions are unwrapped across the edges of a board by following the neighbours of each cell,
+// so objects that cross an edge keep their shape. Clusters without current cells are left out.
+fn clusters<C: Copy + Eq + Hash>(
+    current: &HashSet<C>,
+    mut remaining: HashSet<C>,
+    position: impl Fn(C) -> (i64, i64),
+    resolve: impl Fn(i64, i64) -> Option<C>) -> Vec<Vec<(i64, i64)>> {
+    let mut objects = Vec::new();
+
+    for &start in current {
+        // Cells reached from an earlier start already belong to an object
+        if !remaining.remove(&start) {
+            continue;
+        }
+        let mut cells = Vec::new();
+        let mut queue = VecDeque::from([(position(start), start)]);
+
+        while let Some(((x, y), cell)) = queue.pop_front() {
+            if current.contains(&cell) {
+                cells.push((x, y));
+            }
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if let Some(neighbour) = resolve(x + dx, y + dy) {
+                        if remaining.remove(&neighbour) {
+                            queue.push_back(((x + dx, y + dy), neighbour));
+                        }
+                    }
+                }
+            }
+        }
+
+        objects.push(cells);
+    }
+
+    objects
+}
+
+// Some phases of known objects fall apart into clusters that don't touch, like the spark in front
+// of the lightweight spaceship. Clusters up to two cells apart are merged when they form a known
+// object together.
+fn merge_known(
+    mut objects: Vec<Vec<(i64, i64)>>,
+    known: &HashMap<Vec<(i64, i64)>, &'static str>) -> Vec<Vec<(i64, i64)>> {
+    let mut owners: HashMap<(i64, i64), usize> = HashMap::new();
+    for (index, cells) in objects.iter().enumerate() {
+        owners.extend(cells.iter().map(|&cell| (cell, index)));
+    }
+
+    for index in 0..objects.len() {
+        let mut nearby: Vec<usize> = objects[index].iter()
+            .flat_map(|&(x, y)| (-2..=2).flat_map(move |dy| (-2..=2).map(move |dx| (x + dx, y + dy))))
+            .filter_map(|cell| owners.get(&cell).copied())
+            .filter(|&other| other != index)
+            .collect();
+        nearby.sort_unstable();
+        nearby.dedup();
+
+        for other in nearby {
+            let combined: Vec<(i64, i64)> = objects[index].iter().chain(&objects[other]).copied().collect();
+            if objects[other].is_empty() || !known.contains_key(&canonical_form(&combined)) {
+                continue;
+            }
+            for &cell in &objects[other] {
+                owners.insert(cell, index);
+            }
+            objects[other].clear();
+            objects[index] = combined;
+        }
+    }
+
+    objects.retain(|cells| !cells.is_empty());
+    objects
+}
+
+// The smallest of the sorted cell lists of the eight rotations and reflections, moved to the origin
+fn canonical_form(cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let orientations: [Orientation; 8] = [
+        |(x, y)| (x, y),
+        |(x, y)| (-x, y),
+        |(x, y)| (x, -y),
+        |(x, y)| (-x, -y),
+        |(x, y)| (y, x),
+        |(x, y)| (-y, x),
+        |(x, y)| (y, -x),
+        |(x, y)| (-y, -x),
+    ];
+
+    orientations.iter()
+        .map(|orientation| {
+            let mut oriented: Vec<(i64, i64)> = cells.iter().map(|&cell| orientation(cell)).collect();
+            let left = oriented.iter().map(|&(x, _)| x).min().unwrap_or(0);
+            let top = oriented.iter().map(|&(_, y)| y).min().unwrap_or(0);
+            for cell in oriented.iter_mut() {
+                *cell = (cell.0 - left, cell.1 - top);
+            }
+            oriented.sort_unstable_by_key(|&(x, y)| (y, x));
+            oriented
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn encode(cells: &[(i64, i64)]) -> String {
+    let width = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0) as usize;
+    let height = cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0) as usize;
+    let mut grid = vec![vec![0u8; width]; height];
+    for &(x, y) in cells {
+        grid[y as usize][x as usize] = 1;
+    }
+
+    grid.iter()
+        .map(|row| rle::row_runs(row).into_iter()
+            .map(|(length, tag)| if length > 1 { format!("{}{}", length, tag) } else { tag.to_string() })
+            .collect::<String>())
+        .collect::<Vec<String>>()
+        .join("$")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::direction::Direction;
+
+    fn names(census: &Census) -> Vec<(Option<&'static str>, usize)> {
+        census.entries.iter().map(|entry| (entry.name, entry.count)).collect()
+    }
+
+    fn census_of_grid(grid: &[Vec<u8>], known: &KnownObjects) -> Census {
+        let mut simulation = SparseBoard::new(Rule::conway()).unwrap();
+        simulation.set_cells(grid, 0, 0);
+        Census::of_cells(&simulation.live_cells(), known)
+    }
+
+    // Counts the object in every phase of its period
+    fn assert_every_phase(grid: &[Vec<u8>], period: usize, name: &'static str, known: &KnownObjects) {
+        let mut simulation = SparseBoard::new(Rule::conway()).unwrap();
+        simulation.set_cells(grid, 0, 0);
+        for phase in 0..period {
+            let census = Census::of_cells(&simulation.live_cells(), known);
+            assert_eq!(names(&census), vec![(Some(name), 1)], "phase {} of {}", phase, name);
+            simulation.perform_generation();
+        }
+    }
+
+    #[test]
+    fn names_a_block() {
+        let known = KnownObjects::new(&Rule::conway());
+        let census = census_of_grid(&[vec![1, 1], vec![1, 1]], &known);
+        assert_eq!(names(&census), vec![(Some("block"), 1)]);
+        assert_eq!(census.entries[0].code, "2o$2o");
+        assert_eq!(census.entries[0].population, 4);
+    }
+
+    #[test]
+    fn names_both_phases_of_a_blinker() {
+        let known = KnownObjects::new(&Rule::conway());
+        assert_every_phase(&[vec![1, 1, 1]], 2, "blinker", &known);
+        assert_every_phase(&[vec![1], vec![1], vec![1]], 2, "blinker", &known);
+    }
+
+    #[test]
+    fn names_gliders_in_every_orientation_and_phase() {
+        let known = KnownObjects::new(&Rule::conway());
+        for direction in [Direction::North, Direction::East, Direction::South, Direction::West] {
+            let grid = Pattern::Glider.rotated_grid(direction);
+            assert_every_phase(&grid, 4, "glider", &known);
+
+            let reflected: Vec<Vec<u8>> = grid.iter().map(|row| row.iter().rev().copied().collect()).collect();
+            assert_every_phase(&reflected, 4, "glider", &known);
+        }
+    }
+
+    #[test]
+    fn keeps_a_beacon_together_where_its_blocks_do_not_touch() {
+        let known = KnownObjects::new(&Rule::conway());
+        let separate = vec![vec![1, 1, 0, 0], vec![1, 0, 0, 0], vec![0, 0, 0, 1], vec![0, 0, 1, 1]];
+        assert_eq!(names(&census_of_grid(&separate, &known)), vec![(Some("beacon"), 1)]);
+
+        let mut board = Board::with_size(10, 10);
+        board.set_cells(separate, 3, 3);
+        assert_eq!(names(&Census::of_board(&board, &known)), vec![(Some("beacon"), 1)]);
+    }
+
+    #[test]
+    fn keeps_the_spark_of_a_lightweight_spaceship() {
+        let known = KnownObjects::new(&Rule::conway());
+        let spaceship = plaintext::parse(".O..O\nO....\nO...O\nOOOO.").unwrap().grid;
+        let mut simulation = SparseBoard::new(Rule::conway()).unwrap();
+        simulation.set_cells(&spaceship, 0, 0);
+
+        // The spark splits off the body in one of the phases
+        let mut split = false;
+        for _ in 0..4 {
+            let cells: HashSet<(i64, i64)> = simulation.live_cells().into_iter().collect();
+            split |= clusters(&cells, cells.clone(), |cell| cell, |x, y| Some((x, y))).len() > 1;
+
+            let census = Census::of_cells(&simulation.live_cells(), &known);
+            assert_eq!(names(&census), vec![(Some("lightweight spaceship"), 1)]);
+            simulation.perform_generation();
+        }
+        assert!(split);
+    }
+
+    #[test]
+    fn counts_separate_objects() {
+        let known = KnownObjects::new(&Rule::conway());
+        let grid = vec![vec![1, 1, 0, 0, 0, 1, 1, 1], vec![1, 1, 0, 0, 0, 0, 0, 0], vec![0; 8],
+                        vec![0; 8], vec![1, 1, 1, 0, 0, 0, 0, 0]];
+        let census = census_of_grid(&grid, &known);
+        assert_eq!(names(&census), vec![(Some("blinker"), 2), (Some("block"), 1)]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::simulation::{BoundingBox, Simulation};
+
+/// The longest period found by a `CycleDetector::new`, the states of older generations are forgotten
+pub const DEFAULT_MAX_PERIOD: usize = 1 << 12;
+
+/// How a pattern repeats once it has settled. `since` is the first generation of the repeating
+/// states, numbered like `Board::generation` which starts at 1 on a new board.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Cycle {
+    Extinct { since: i128 },
+    StillLife { since: i128 },
+    Oscillator { period: i128, since: i128 },
+    // The pattern reappears moved by dx and dy cells every period generations
+    Spaceship { dx: i64, dy: i64, period: i128, since: i128 },
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Cycle::Extinct { since } =>
+                write!(f, "Extinct since generation {}", since),
+            Cycle::StillLife { since } =>
+                write!(f, "Still life since generation {}", since),
+            Cycle::Oscillator { period, since } =>
+                write!(f, "Oscillator with period {} since generation {}", period, since),
+            Cycle::Spaceship { dx, dy, period, since } =>
+                write!(f, "Spaceship moving ({}, {}) with period {} since generation {}", dx, dy, period, since),
+        }
+    }
+}
+
+// The live cells of a generation relative to their bounding box, shared by both collections of states
+type State = Arc<[(i64, i64)]>;
+
+/// Detects when a simulation re-enters an earlier state, possibly translated. The states have to
+/// be observed one generation after the other, skipping a generation starts the detection over.
+///
+/// Spaceships are recognised by comparing states relative to their bounding box, on a wrapping
+/// board a spaceship crossing the edge is only found once it returns to the exact same state.
+pub struct CycleDetector {
+    // Each state with the generation and position of its bounding box
+    states: HashMap<State, (i128, i64, i64)>,
+    // The same states, oldest first, at most one per generation up to the longest period
+    recent: VecDeque<State>,
+    max_period: usize,
+    last_generation: Option<i128>,
+    cycle: Option<Cycle>,
+}
+
+impl Default for CycleDetector {
+    fn default() -> Self {
+        CycleDetector::new()
+    }
+}
+
+impl CycleDetector {
+    pub fn new() -> Self {
+        CycleDetector::with_max_period(DEFAULT_MAX_PERIOD)
+    }
+
+    /// Only finds cycles up to the given period, so the memory used stays bounded however long the
+    /// simulation runs
+    pub fn with_max_period(max_period: usize) -> Self {
+        CycleDetector {
+            states: HashMap::new(),
+            recent: VecDeque::new(),
+            max_period: max_period.max(1),
+            last_generation: None,
+            cycle: None,
+        }
+    }
+
+    /// Forgets all observed states, needed whenever the board is changed other than by stepping it
+    pub fn reset(&mut self) {
+        self.states.clear();
+        self.recent.clear();
+        self.last_generation = None;
+        self.cycle = None;
+    }
+
+    /// The cycle the observed states have settled into, if any
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    pub fn observe(&mut self, board: &Board) -> Option<Cycle> {
+        let cells = board.live_cells().map(|(x, y)| (x as i64, y as i64));
+        self.observe_cells(board.generation(), board.bounding_box(), cells)
+    }
+
+    pub fn observe_simulation(&mut self, simulation: &dyn Simulation) -> Option<Cycle> {
+        // Compared in the same row by row order as the cells of a board
+        let mut cells = simulation.live_cells();
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        self.observe_cells(simulation.generation(), simulation.bounding_box(), cells.into_iter())
+    }
+
+    fn observe_cells(
+        &mut self,
+        generation: i128,
+        bounding_box: Option<BoundingBox>,
+        cells: impl Iterator<Item=(i64, i64)>) -> Option<Cycle> {
+        match self.last_generation {
+            Some(last) if last == generation => return self.cycle,
+            Some(last) if last + 1 != generation => self.reset(),
+            _ => (),
+        }
+        self.last_generation = Some(generation);
+        if self.cycle.is_some() {
+            return self.cycle;
+        }
+
+        let bounding_box = match bounding_box {
+            Some(bounding_box) => bounding_box,
+            None => {
+                self.cycle = Some(Cycle::Extinct { since: generation });
+                return self.cycle;
+            }
+        };
+
+        // The bounding box is the smallest one around the cells, so the relative cells also fix its size
+        let state: State = cells.map(|(x, y)| (x - bounding_box.x, y - bounding_box.y)).collect();
+
+        if let Some(&(since, x, y)) = self.states.get(&state) {
+            let period = generation - since;
+            let (dx, dy) = (bounding_box.x - x, bounding_box.y - y);
+            self.cycle = Some(match (dx, dy, period) {
+                (0, 0, 1) => Cycle::StillLife { since },
+                (0, 0, _) => Cycle::Oscillator { period, since },
+                _ => Cycle::Spaceship { dx, dy, period, since },
+            });
+            return self.cycle;
+        }
+
+        self.states.insert(state.clone(), (generation, bounding_box.x, bounding_box.y));
+        self.recent.push_back(state);
+        if self.recent.len() > self.max_period {
+            let oldest = self.recent.pop_front().unwrap();
+            self.states.remove(&oldest);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+    use crate::simulation::perform_generation;
+    use crate::sparse::SparseBoard;
+    use crate::topology::Topology;
+
+    // Steps a board holding the grid until the detector reports a cycle
+    fn settle(grid: Vec<Vec<u8>>) -> Cycle {
+        let rule = Rule::conway();
+        let mut board = Board::with_size(64, 64);
+        board.topology = Topology::Plane;
+        board.set_cells(grid, 32, 32);
+
+        let mut cycles = CycleDetector::new();
+        for _ in 0..100 {
+            if let Some(cycle) = cycles.observe(&board) {
+                return cycle;
+            }
+            board = perform_generation(&board, &rule, 1);
+        }
+        panic!("No cycle found");
+    }
+
+    #[test]
+    fn finds_a_still_life() {
+        assert_eq!(settle(vec![vec![1, 1], vec![1, 1]]), Cycle::StillLife { since: 1 });
+    }
+
+    #[test]
+    fn finds_an_oscillator() {
+        assert_eq!(settle(vec![vec![1, 1, 1]]), Cycle::Oscillator { period: 2, since: 1 });
+        // A toad
+        assert_eq!(settle(vec![vec![1, 1, 1, 0], vec![0, 1, 1, 1]]), Cycle::Oscillator { period: 2, since: 1 });
+    }
+
+    #[test]
+    fn finds_a_spaceship_placed_on_a_new_board() {
+        let glider = vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]];
+        assert_eq!(settle(glider), Cycle::Spaceship { dx: 1, dy: 1, period: 4, since: 1 });
+    }
+
+    #[test]
+    fn finds_the_extinction() {
+        assert_eq!(settle(vec![vec![1, 1]]), Cycle::Extinct { since: 2 });
+    }
+
+    #[test]
+    fn finds_the_first_repeating_state() {
+        // The pre-block settles into a block after one generation
+        assert_eq!(settle(vec![vec![1, 1], vec![1, 0]]), Cycle::StillLife { since: 2 });
+    }
+
+    #[test]
+    fn starts_over_after_skipped_generations() {
+        let mut simulation = SparseBoard::new(Rule::conway()).unwrap();
+        simulation.set_cells(&[vec![1, 1, 1]], 0, 0);
+        let mut cycles = CycleDetector::new();
+
+        assert_eq!(cycles.observe_simulation(&simulation), None);
+        simulation.step(2).unwrap();
+        assert_eq!(cycles.observe_simulation(&simulation), None);
+        simulation.step(1).unwrap();
+        assert_eq!(cycles.observe_simulation(&simulation), None);
+        simulation.step(1).unwrap();
+        assert_eq!(cycles.observe_simulation(&simulation), Some(Cycle::Oscillator { period: 2, since: 3 }));
+    }
+
+    #[test]
+    fn forgets_states_older_than_the_longest_period() {
+        let mut simulation = SparseBoard::new(Rule::conway()).unwrap();
+        simulation.set_cells(&[vec![1, 1, 1]], 0, 0);
+        let mut cycles = CycleDetector::with_max_period(1);
+
+        for _ in 0..10 {
+            assert_eq!(cycles.observe_simulation(&simulation), None);
+            simulation.step(1).unwrap();
+        }
+    }
+}
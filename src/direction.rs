@@ -1,4 +1,4 @@
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use strum::{EnumCount, FromRepr};
 
 #[derive(FromRepr, Debug, PartialEq, EnumCount)]
@@ -10,8 +10,7 @@ pub enum Direction {
 }
 
 impl Direction{
-    pub fn get_random_direction() -> Direction {
-        let mut rng = thread_rng();
+    pub fn get_random_direction(rng: &mut impl Rng) -> Direction {
         return Direction::from_repr(rng.gen_range(0..Direction::COUNT))
             .unwrap_or(Direction::North);
     }
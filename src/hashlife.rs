@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+
+use crate::rule::Rule;
+use crate::simulation::{BoundingBox, Simulation};
+
+type NodeId = u32;
+
+// Leaf nodes are single cells, every other node is a square of 2^level cells on each side made
+// of four canonical child nodes
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+const MIN_LEVEL: u8 = 3;
+// The root never grows beyond this level, so that every coordinate of the plane fits an i64
+const MAX_LEVEL: u8 = 62;
+// The memo is collected once it holds this many nodes, about 200 MB
+const MAX_NODES: usize = 1 << 22;
+
+#[derive(Copy, Clone)]
+struct Node {
+    level: u8,
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+    population: u128,
+}
+
+/// Simulates an unbounded plane with the HashLife algorithm, which memoizes the future of every
+/// distinct quadtree node and can advance repetitive patterns by huge numbers of generations. The
+/// plane spans 2^62 cells on each side, centered on the origin.
+pub struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    canonical: HashMap<(NodeId, NodeId, NodeId, NodeId), NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    origin: (i64, i64),
+    generation: i128,
+    // Number of nodes above which the memo is collected
+    node_limit: usize,
+}
+
+impl HashLife {
+    /// Rejects the same rules as `SparseBoard::new`
+    pub fn new(rule: Rule) -> Result<Self, String> {
+        if rule.is_born(0) {
+            return Err(format!("HashLife does not support rule {} with birth on 0 neighbours", rule));
+        }
+
+        let leaf = |population| Node { level: 0, nw: DEAD, ne: DEAD, sw: DEAD, se: DEAD, population };
+        let mut hashlife = HashLife {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            canonical: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: (0, 0),
+            generation: 1,
+            node_limit: MAX_NODES,
+        };
+
+        let half = 1i64 << (MIN_LEVEL - 1);
+        hashlife.root = hashlife.empty_node(MIN_LEVEL);
+        hashlife.origin = (-half, -half);
+        Ok(hashlife)
+    }
+
+    /// Advances the simulation by 2^power generations in a single jump, fails when the pattern
+    /// grows beyond the plane
+    pub fn step_power_of_two(&mut self, power: u8) -> Result<(), String> {
+        // Jumps too large for the largest root are made in two halves
+        if power > MAX_LEVEL - 3 {
+            self.step_power_of_two(power - 1)?;
+            return self.step_power_of_two(power - 1);
+        }
+        if self.nodes.len() > self.node_limit {
+            self.collect_garbage();
+        }
+
+        // The pattern has to sit in the central quarter of the root, with the root large enough
+        // that the pattern can't grow out of the result during the jump
+        while self.level(self.root) < power + 2 || !self.is_padded(self.root) {
+            self.expand()?;
+        }
+        self.expand()?;
+
+        let level = self.level(self.root);
+        let offset = 1i64 << (level - 2);
+        self.root = self.successor(self.root, power);
+        self.origin = (self.origin.0 + offset, self.origin.1 + offset);
+        self.generation += 1i128 << power;
+        Ok(())
+    }
+
+    // Drops every node and result not needed by the root, copying the nodes of the root to new
+    // tables. Patterns with more nodes than the limit raise it, so they aren't collected every step.
+    fn collect_garbage(&mut self) {
+        let leaves = self.nodes[..2].to_vec();
+        let old = std::mem::replace(&mut self.nodes, leaves);
+        self.canonical.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+
+        let mut copied = HashMap::from([(DEAD, DEAD), (ALIVE, ALIVE)]);
+        self.root = self.copy_node(&old, self.root, &mut copied);
+        self.node_limit = MAX_NODES.max(self.nodes.len() * 2);
+    }
+
+    fn copy_node(&mut self, old: &[Node], node: NodeId, copied: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if let Some(&id) = copied.get(&node) {
+            return id;
+        }
+
+        let n = old[node as usize];
+        let [nw, ne, sw, se] = [n.nw, n.ne, n.sw, n.se].map(|child| self.copy_node(old, child, copied));
+        let id = self.join(nw, ne, sw, se);
+        copied.insert(node, id);
+        id
+    }
+
+    fn level(&self, node: NodeId) -> u8 {
+        self.nodes[node as usize].level
+    }
+
+    fn population_of(&self, node: NodeId) -> u128 {
+        self.nodes[node as usize].population
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        if let Some(&node) = self.canonical.get(&(nw, ne, sw, se)) {
+            return node;
+        }
+
+        let population = self.population_of(nw) + self.population_of(ne)
+            + self.population_of(sw) + self.population_of(se);
+        let node = Node { level: self.level(nw) + 1, nw, ne, sw, se, population };
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.canonical.insert((nw, ne, sw, se), id);
+        id
+    }
+
+    fn empty_node(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let child = *self.empty.last().unwrap();
+            let node = self.join(child, child, child, child);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    // Wraps the root in a node of twice its size, keeping the root in the center
+    fn expand(&mut self) -> Result<(), String> {
+        let root = self.nodes[self.root as usize];
+        if root.level >= MAX_LEVEL {
+            return Err(format!("The pattern has grown beyond the HashLife plane of 2^{} cells on each side",
+                               MAX_LEVEL));
+        }
+        let border = self.empty_node(root.level - 1);
+
+        let nw = self.join(border, border, border, root.nw);
+        let ne = self.join(border, border, root.ne, border);
+        let sw = self.join(border, root.sw, border, border);
+        let se = self.join(root.se, border, border, border);
+        self.root = self.join(nw, ne, sw, se);
+
+        let offset = 1i64 << (root.level - 1);
+        self.origin = (self.origin.0 - offset, self.origin.1 - offset);
+        Ok(())
+    }
+
+    fn is_padded(&self, node: NodeId) -> bool {
+        let node = self.nodes[node as usize];
+        let inner = self.population_of(self.nodes[node.nw as usize].se)
+            + self.population_of(self.nodes[node.ne as usize].sw)
+            + self.population_of(self.nodes[node.sw as usize].ne)
+            + self.population_of(self.nodes[node.se as usize].nw);
+        inner == node.population
+    }
+
+    // The center node of the given node, half its size
+    fn center(&mut self, node: NodeId) -> NodeId {
+        let node = self.nodes[node as usize];
+        let nw = self.nodes[node.nw as usize].se;
+        let ne = self.nodes[node.ne as usize].sw;
+        let sw = self.nodes[node.sw as usize].ne;
+        let se = self.nodes[node.se as usize].nw;
+        self.join(nw, ne, sw, se)
+    }
+
+    // The center of the given node, advanced by 2^power generations
+    fn successor(&mut self, node: NodeId, power: u8) -> NodeId {
+        let level = self.level(node);
+        let power = power.min(level - 2);
+
+        if self.population_of(node) == 0 {
+            return self.empty_node(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(node, power)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.successor_of_leaves(node)
+        } else {
+            self.successor_of_children(node, power)
+        };
+
+        self.results.insert((node, power), result);
+        result
+    }
+
+    // Computes a single generation of the center 2x2 cells of a 4x4 node
+    fn successor_of_leaves(&mut self, node: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        let node = self.nodes[node as usize];
+
+        for (index, &child) in [node.nw, node.ne, node.sw, node.se].iter().enumerate() {
+            let child = self.nodes[child as usize];
+            let (x, y) = ((index % 2) * 2, (index / 2) * 2);
+            cells[y][x] = child.nw == ALIVE;
+            cells[y][x + 1] = child.ne == ALIVE;
+            cells[y + 1][x] = child.sw == ALIVE;
+            cells[y + 1][x + 1] = child.se == ALIVE;
+        }
+
+        let mut next = [DEAD; 4];
+        for (index, (x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+            let neighbours = cells[y - 1..=y + 1].iter()
+                .flat_map(|row| &row[x - 1..=x + 1])
+                .filter(|&&cell| cell)
+                .count() as i8 - cells[y][x] as i8;
+            if self.rule.next_state(cells[y][x], neighbours) {
+                next[index] = ALIVE;
+            }
+        }
+
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    fn successor_of_children(&mut self, node: NodeId, power: u8) -> NodeId {
+        let level = self.level(node);
+        let n = self.nodes[node as usize];
+        let (nw, ne, sw, se) = (
+            self.nodes[n.nw as usize], self.nodes[n.ne as usize],
+            self.nodes[n.sw as usize], self.nodes[n.se as usize]
+        );
+
+        // Nine overlapping sub nodes of half the size, covering the node in a 3x3 grid
+        let n00 = n.nw;
+        let n01 = self.join(nw.ne, ne.nw, nw.se, ne.sw);
+        let n02 = n.ne;
+        let n10 = self.join(nw.sw, nw.se, sw.nw, sw.ne);
+        let n11 = self.join(nw.se, ne.sw, sw.ne, se.nw);
+        let n12 = self.join(ne.sw, ne.se, se.nw, se.ne);
+        let n20 = n.sw;
+        let n21 = self.join(sw.ne, se.nw, sw.se, se.sw);
+        let n22 = n.se;
+
+        let grid = [n00, n01, n02, n10, n11, n12, n20, n21, n22];
+        let mut stepped = [DEAD; 9];
+        for (index, &sub_node) in grid.iter().enumerate() {
+            stepped[index] = self.successor(sub_node, power);
+        }
+        let [s00, s01, s02, s10, s11, s12, s20, s21, s22] = stepped;
+
+        let quadrants = [
+            self.join(s00, s01, s10, s11),
+            self.join(s01, s02, s11, s12),
+            self.join(s10, s11, s20, s21),
+            self.join(s11, s12, s21, s22),
+        ];
+
+        let mut result = [DEAD; 4];
+        for (index, &quadrant) in quadrants.iter().enumerate() {
+            // A full speed step advances twice, a slower step only takes the centers
+            result[index] = if power == level - 2 {
+                self.successor(quadrant, power)
+            } else {
+                self.center(quadrant)
+            };
+        }
+
+        self.join(result[0], result[1], result[2], result[3])
+    }
+
+    fn set_in(&mut self, node: NodeId, x: i64, y: i64, value: bool) -> NodeId {
+        let n = self.nodes[node as usize];
+        if n.level == 0 {
+            return if value { ALIVE } else { DEAD };
+        }
+
+        let half = 1i64 << (n.level - 1);
+        match (x < half, y < half) {
+            (true, true) => {
+                let nw = self.set_in(n.nw, x, y, value);
+                self.join(nw, n.ne, n.sw, n.se)
+            }
+            (false, true) => {
+                let ne = self.set_in(n.ne, x - half, y, value);
+                self.join(n.nw, ne, n.sw, n.se)
+            }
+            (true, false) => {
+                let sw = self.set_in(n.sw, x, y - half, value);
+                self.join(n.nw, n.ne, sw, n.se)
+            }
+            (false, false) => {
+                let se = self.set_in(n.se, x - half, y - half, value);
+                self.join(n.nw, n.ne, n.sw, se)
+            }
+        }
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let size = 1i64 << self.level(self.root);
+        x >= self.origin.0 && y >= self.origin.1
+            && x < self.origin.0 + size && y < self.origin.1 + size
+    }
+
+    fn collect_cells(&self, node: NodeId, x: i64, y: i64, cells: &mut Vec<(i64, i64)>) {
+        let n = self.nodes[node as usize];
+        if n.population == 0 {
+            return;
+        }
+        if n.level == 0 {
+            cells.push((x, y));
+            return;
+        }
+
+        let half = 1i64 << (n.level - 1);
+        self.collect_cells(n.nw, x, y, cells);
+        self.collect_cells(n.ne, x + half, y, cells);
+        self.collect_cells(n.sw, x, y + half, cells);
+        self.collect_cells(n.se, x + half, y + half, cells);
+    }
+
+    // Bounds of the alive cells relative to the node, as inclusive (left, top, right, bottom)
+    fn bounds_of(&self, node: NodeId, memo: &mut HashMap<NodeId, (i64, i64, i64, i64)>)
+                 -> Option<(i64, i64, i64, i64)> {
+        let n = self.nodes[node as usize];
+        if n.population == 0 {
+            return None;
+        }
+        if n.level == 0 {
+            return Some((0, 0, 0, 0));
+        }
+        if let Some(&bounds) = memo.get(&node) {
+            return Some(bounds);
+        }
+
+        let half = 1i64 << (n.level - 1);
+        let children = [(n.nw, 0, 0), (n.ne, half, 0), (n.sw, 0, half), (n.se, half, half)];
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+
+        for (child, x, y) in children {
+            if let Some((left, top, right, bottom)) = self.bounds_of(child, memo) {
+                let (left, top, right, bottom) = (left + x, top + y, right + x, bottom + y);
+                bounds = Some(match bounds {
+                    None => (left, top, right, bottom),
+                    Some(b) => (b.0.min(left), b.1.min(top), b.2.max(right), b.3.max(bottom)),
+                });
+            }
+        }
+
+        memo.insert(node, bounds.unwrap());
+        bounds
+    }
+}
+
+impl Simulation for HashLife {
+    /// Advances by the given number of generations, one power of two jump per set bit
+    fn step(&mut self, generations: u64) -> Result<(), String> {
+        for power in (0..64).rev() {
+            if generations & (1u64 << power) != 0 {
+                self.step_power_of_two(power)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn generation(&self) -> i128 {
+        self.generation
+    }
+
+    fn population(&self) -> i128 {
+        self.population_of(self.root) as i128
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let (left, top, right, bottom) = self.bounds_of(self.root, &mut HashMap::new())?;
+        Some(BoundingBox {
+            x: self.origin.0 + left,
+            y: self.origin.1 + top,
+            width: (right - left + 1) as u64,
+            height: (bottom - top + 1) as u64,
+        })
+    }
+
+    /// Cells beyond the plane are ignored, like cells outside a board
+    fn set_cell(&mut self, x: i64, y: i64, value: bool) {
+        while !self.contains(x, y) {
+            if self.expand().is_err() {
+                return;
+            }
+        }
+        self.root = self.set_in(self.root, x - self.origin.0, y - self.origin.1, value);
+    }
+
+    fn is_cell_occupied(&self, x: i64, y: i64) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+
+        let mut node = self.nodes[self.root as usize];
+        let (mut x, mut y) = (x - self.origin.0, y - self.origin.1);
+
+        while node.level > 0 {
+            let half = 1i64 << (node.level - 1);
+            let child = match (x < half, y < half) {
+                (true, true) => node.nw,
+                (false, true) => node.ne,
+                (true, false) => node.sw,
+                (false, false) => node.se,
+            };
+            if x >= half {
+                x -= half;
+            }
+            if y >= half {
+                y -= half;
+            }
+            node = self.nodes[child as usize];
+        }
+        node.population == 1
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        self.collect_cells(self.root, self.origin.0, self.origin.1, &mut cells);
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::sparse::SparseBoard;
+
+    const R_PENTOMINO: [(i64, i64); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+    const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    fn simulations(rule: &str, cells: &[(i64, i64)]) -> (HashLife, SparseBoard) {
+        let rule = Rule::parse(rule).unwrap();
+        let (mut hashlife, mut sparse) = (HashLife::new(rule).unwrap(), SparseBoard::new(rule).unwrap());
+        for &(x, y) in cells {
+            hashlife.set_cell(x, y, true);
+            sparse.set_cell(x, y, true);
+        }
+        (hashlife, sparse)
+    }
+
+    fn sorted_cells(simulation: &dyn Simulation) -> Vec<(i64, i64)> {
+        let mut cells = simulation.live_cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn assert_same(hashlife: &HashLife, sparse: &SparseBoard) {
+        assert_eq!(hashlife.generation(), sparse.generation());
+        assert_eq!(hashlife.population(), sparse.population());
+        assert_eq!(hashlife.bounding_box(), sparse.bounding_box());
+        assert_eq!(sorted_cells(hashlife), sorted_cells(sparse), "generation {}", hashlife.generation());
+    }
+
+    #[test]
+    fn matches_sparse_board_after_every_number_of_generations() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let soup: Vec<(i64, i64)> = (0..256).filter(|_| rng.gen_bool(0.5))
+            .map(|index| (index % 16, index / 16))
+            .collect();
+
+        for (rule, cells) in [("B3/S23", &R_PENTOMINO[..]), ("B3/S23", &soup[..]), ("B36/S23", &soup[..])] {
+            for generations in 1..=100 {
+                let (mut hashlife, mut sparse) = simulations(rule, cells);
+                hashlife.step(generations).unwrap();
+                sparse.step(generations).unwrap();
+                assert_same(&hashlife, &sparse);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_sparse_board_over_consecutive_jumps() {
+        let (mut hashlife, mut sparse) = simulations("B3/S23", &R_PENTOMINO);
+        for generations in [1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377] {
+            hashlife.step(generations).unwrap();
+            sparse.step(generations).unwrap();
+            assert_same(&hashlife, &sparse);
+        }
+    }
+
+    #[test]
+    fn keeps_the_pattern_when_collecting_garbage() {
+        let (mut hashlife, mut sparse) = simulations("B3/S23", &R_PENTOMINO);
+        for _ in 0..10 {
+            hashlife.step(50).unwrap();
+            sparse.step(50).unwrap();
+            hashlife.collect_garbage();
+            assert_same(&hashlife, &sparse);
+        }
+    }
+
+    #[test]
+    fn fails_when_the_pattern_leaves_the_plane() {
+        let (mut hashlife, _) = simulations("B3/S23", &GLIDER);
+        hashlife.step(1 << 40).unwrap();
+        assert_eq!(hashlife.bounding_box().map(|bounds| (bounds.x, bounds.y)), Some((1 << 38, 1 << 38)));
+        assert!(hashlife.step(u64::MAX).is_err());
+
+        let (mut hashlife, _) = simulations("B3/S23", &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        hashlife.step(u64::MAX).unwrap();
+        assert_eq!(hashlife.population(), 4);
+        assert_eq!(hashlife.generation(), 1 + u64::MAX as i128);
+    }
+}
@@ -0,0 +1,161 @@
+use game_of_life::board::Board;
+use game_of_life::hashlife::HashLife;
+use game_of_life::heatmap::Heatmap;
+use game_of_life::pattern::PatternFile;
+use game_of_life::recording::Recorder;
+use game_of_life::rule::Rule;
+use game_of_life::render::save_png;
+use game_of_life::simulation::{BoundingBox, Engine, GridSimulation, Simulation};
+use game_of_life::sparse::SparseBoard;
+
+use crate::options::Options;
+
+/// Runs the simulation without a window, printing the state of the board to stdout
+pub fn run(options: &Options) -> Result<(), String> {
+    let pattern = match (&options.pattern, options.soup) {
+        (Some(path), _) => PatternFile::load(path)?,
+        (None, Some(_)) => {
+            let seed = options.seed.unwrap_or_else(rand::random);
+            println!("# soup seed {}", seed);
+            PatternFile { grid: options.soup(seed).grid()?, ..PatternFile::default() }
+        }
+        (None, None) => return Err("Headless mode requires a --pattern file or a --soup".to_string()),
+    };
+    let rule = options.rule.or(pattern.rule).unwrap_or_default();
+
+    let mut simulation = create_simulation(options, rule)?;
+    let (x, y) = match options.engine {
+        Engine::Grid => ((options.width / 2) as i64, (options.height / 2) as i64),
+        _ => (0, 0),
+    };
+    simulation.set_cells(&pattern.grid, x, y);
+
+    println!("# rule {} engine {}", rule, options.engine.key());
+    println!("# generation population x y width height");
+    print_state(simulation.as_ref());
+
+    // Boards are drawn whole, unbounded simulations around their live cells
+    let mut snapshot = options.snapshot;
+    if let (None, Engine::Grid) = (snapshot.region, options.engine) {
+        let (width, height) = (options.width as u64, options.height as u64);
+        snapshot.region = Some(BoundingBox { x: 0, y: 0, width, height });
+    }
+
+    let first_generation = simulation.generation();
+    let frames = FrameRange {
+        from: options.record_from.map_or(first_generation, |from| (from as i128).max(first_generation)),
+        to: options.record_to.map(|to| to as i128),
+        every: options.record_every as i128,
+    };
+    let mut recorder = options.record.as_ref()
+        .map(|_| Recorder::new(snapshot, options.record_every, options.frame_delay));
+    if let Some(recorder) = &mut recorder {
+        frames.capture(recorder, simulation.as_ref());
+    }
+
+    // The heatmap covers the same area as the images and counts the last generations of the run,
+    // which are stepped one at a time
+    let mut heatmap = match (&options.heatmap, snapshot.region) {
+        (None, _) => None,
+        (Some(_), Some(region)) => Some(Heatmap::new(options.heatmap_measure, options.heatmap_window, region)),
+        (Some(_), None) => return Err(format!("A heatmap of the {} engine needs a --region",
+                                              options.engine.key())),
+    };
+    let heatmap_from = options.generations.saturating_sub(options.heatmap_window as u64);
+    if let (Some(heatmap), 0) = (&mut heatmap, heatmap_from) {
+        heatmap.observe_simulation(simulation.as_ref());
+    }
+
+    // Steps up to the next line to print, frame to record or generation to count in the heatmap,
+    // whichever comes first
+    let every = options.every.unwrap_or(options.generations).max(1);
+    let mut done = 0;
+    while done < options.generations {
+        let mut generations = (every - done % every).min(options.generations - done);
+        if let (Some(_), Some(next)) = (&recorder, frames.next(simulation.generation())) {
+            generations = generations.min((next - simulation.generation()) as u64);
+        }
+        if heatmap.is_some() {
+            generations = if done < heatmap_from { generations.min(heatmap_from - done) } else { 1 };
+        }
+        simulation.step(generations)?;
+        done += generations;
+
+        if let Some(heatmap) = &mut heatmap {
+            if done >= heatmap_from {
+                heatmap.observe_simulation(simulation.as_ref());
+            }
+        }
+
+        if done % every == 0 || done == options.generations {
+            print_state(simulation.as_ref());
+        }
+        if let Some(recorder) = &mut recorder {
+            frames.capture(recorder, simulation.as_ref());
+        }
+    }
+
+    if let (Some(path), Some(recorder)) = (&options.record, &recorder) {
+        recorder.save(path)?;
+    }
+    if let Some(path) = &options.png {
+        save_png(&snapshot.render_simulation(simulation.as_ref())?, path)?;
+    }
+    if let (Some(path), Some(heatmap)) = (&options.heatmap, &heatmap) {
+        heatmap.save(path, snapshot.cell_size)?;
+    }
+
+    Ok(())
+}
+
+// The generations that are recorded, from the first one every so many up to the last one
+struct FrameRange {
+    from: i128,
+    to: Option<i128>,
+    every: i128,
+}
+
+impl FrameRange {
+    fn next(&self, generation: i128) -> Option<i128> {
+        let next = if generation < self.from {
+            self.from
+        } else {
+            self.from + ((generation - self.from) / self.every + 1) * self.every
+        };
+        match self.to {
+            Some(to) if next > to => None,
+            _ => Some(next),
+        }
+    }
+
+    fn capture(&self, recorder: &mut Recorder, simulation: &dyn Simulation) {
+        let generation = simulation.generation();
+        if generation >= self.from && self.to.is_none_or(|to| generation <= to) {
+            recorder.capture_simulation(simulation);
+        }
+    }
+}
+
+fn create_simulation(options: &Options, rule: Rule) -> Result<Box<dyn Simulation>, String> {
+    Ok(match options.engine {
+        Engine::Grid => {
+            let mut board = Board::with_size(options.width, options.height);
+            board.topology = options.topology;
+
+            let mut simulation = GridSimulation::new(board, rule);
+            simulation.threads = options.threads;
+            Box::new(simulation)
+        }
+        Engine::HashLife => Box::new(HashLife::new(rule)?),
+        Engine::Sparse => Box::new(SparseBoard::new(rule)?),
+    })
+}
+
+fn print_state(simulation: &dyn Simulation) {
+    match simulation.bounding_box() {
+        Some(bounds) => println!("{} {} {} {} {} {}", simulation.generation(),
+                                 simulation.population(), bounds.x, bounds.y, bounds.width,
+                                 bounds.height),
+        None => println!("{} 0 - - 0 0", simulation.generation()),
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+use strum::{EnumCount, FromRepr};
+
+use crate::board::Board;
+use crate::render::{fit_cell_size, save_png, BACKGROUND};
+use crate::simulation::{BoundingBox, Simulation};
+
+/// What the heatmap counts for each cell
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum HeatmapMeasure {
+    // Generations in which the cell was born or died
+    Changes,
+    // Generations in which the cell was alive
+    Alive,
+}
+
+impl HeatmapMeasure {
+    pub fn parse(name: &str) -> Result<HeatmapMeasure, String> {
+        (0..HeatmapMeasure::COUNT)
+            .filter_map(HeatmapMeasure::from_repr)
+            .find(|measure| measure.key() == name.trim().to_lowercase())
+            .ok_or(format!("Unknown heatmap measure '{}', expected changes or alive", name))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match *self {
+            HeatmapMeasure::Changes => "changes",
+            HeatmapMeasure::Alive => "alive",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HeatmapMeasure::Changes => "Changes",
+            HeatmapMeasure::Alive => "Alive",
+        }
+    }
+}
+
+/// Counts per cell of a region how often it changed or was alive over the last generations, which
+/// shows where a board is active. Generations are observed the same way as by `CellAges`, the
+/// counts start over on a jump.
+pub struct Heatmap {
+    pub measure: HeatmapMeasure,
+    // Number of most recent generations counted
+    pub window: usize,
+    region: BoundingBox,
+    counts: Vec<u32>,
+    // The cells counted in each generation of the window, oldest first, one bit per cell
+    counted: VecDeque<Vec<u64>>,
+    // The live cells of the last observed generation
+    previous: Vec<u64>,
+    last_generation: Option<i128>,
+}
+
+impl Heatmap {
+    pub fn new(measure: HeatmapMeasure, window: usize, region: BoundingBox) -> Self {
+        let cells = (region.width * region.height) as usize;
+        Heatmap {
+            measure,
+            window: window.max(1),
+            region,
+            counts: vec![0; cells],
+            counted: VecDeque::new(),
+            previous: vec![0; cells.div_ceil(64)],
+            last_generation: None,
+        }
+    }
+
+    /// A heatmap of a whole board
+    pub fn of_board(measure: HeatmapMeasure, window: usize, board: &Board) -> Self {
+        Heatmap::new(measure, window, board_region(board))
+    }
+
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+        self.counted.clear();
+        self.last_generation = None;
+    }
+
+    pub fn region(&self) -> BoundingBox {
+        self.region
+    }
+
+    /// Number of generations counted so far, up to the window
+    pub fn generations(&self) -> usize {
+        self.counted.len()
+    }
+
+    /// The highest count of any cell
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// The count of a cell, 0 for cells outside the region
+    pub fn count(&self, x: i64, y: i64) -> u32 {
+        self.index(x, y).map_or(0, |index| self.counts[index])
+    }
+
+    /// Starts over when the board has a different size than the region of the heatmap
+    pub fn observe(&mut self, board: &Board) {
+        if board_region(board) != self.region {
+            *self = Heatmap::of_board(self.measure, self.window, board);
+        }
+        let cells = board.live_cells().map(|(x, y)| (x as i64, y as i64));
+        self.observe_cells(board.generation(), cells);
+    }
+
+    pub fn observe_simulation(&mut self, simulation: &dyn Simulation) {
+        self.observe_cells(simulation.generation(), simulation.live_cells().into_iter());
+    }
+
+    // Counts the next generation, takes over cells drawn or erased in the same generation without
+    // counting them, and starts over on any other generation
+    fn observe_cells(&mut self, generation: i128, cells: impl Iterator<Item=(i64, i64)>) {
+        let mut live = vec![0u64; self.previous.len()];
+        for (x, y) in cells {
+            if let Some(index) = self.index(x, y) {
+                live[index / 64] |= 1 << (index % 64);
+            }
+        }
+
+        match self.last_generation {
+            Some(last) if generation == last + 1 => {
+                let counted: Vec<u64> = match self.measure {
+                    HeatmapMeasure::Changes => live.iter().zip(&self.previous).map(|(a, b)| a ^ b).collect(),
+                    HeatmapMeasure::Alive => live.clone(),
+                };
+                add_counts(&mut self.counts, &counted, 1);
+                self.counted.push_back(counted);
+
+                while self.counted.len() > self.window {
+                    let oldest = self.counted.pop_front().unwrap();
+                    add_counts(&mut self.counts, &oldest, -1);
+                }
+            }
+            Some(last) if generation == last => (),
+            _ => self.reset(),
+        }
+
+        self.previous = live;
+        self.last_generation = Some(generation);
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        let (x, y) = (x - self.region.x, y - self.region.y);
+        if x < 0 || y < 0 || x >= self.region.width as i64 || y >= self.region.height as i64 {
+            return None;
+        }
+        Some(y as usize * self.region.width as usize + x as usize)
+    }
+
+    /// The counts as rows of comma separated values, one row of cells per line
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.counts.chunks(self.region.width as usize) {
+            let values: Vec<String> = row.iter().map(|count| count.to_string()).collect();
+            csv.push_str(&values.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Draws each cell in the colour of its count relative to the highest count, with smaller cells
+    /// for large regions like a snapshot
+    pub fn render(&self, cell_size: u32) -> Result<RgbImage, String> {
+        let cell_size = fit_cell_size(cell_size, self.region.width, self.region.height)?;
+        let width = self.region.width as usize;
+        let max = self.max().max(1);
+
+        Ok(RgbImage::from_fn(width as u32 * cell_size, self.region.height as u32 * cell_size, |x, y| {
+            match self.counts[(y / cell_size) as usize * width + (x / cell_size) as usize] {
+                0 => BACKGROUND,
+                count => heat_colour(count as f64 / max as f64),
+            }
+        }))
+    }
+
+    /// Saves the counts as CSV for the .csv extension and as a PNG image otherwise
+    pub fn save(&self, path: &Path, cell_size: u32) -> Result<(), String> {
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        match extension.to_lowercase().as_str() {
+            "csv" => fs::write(path, self.to_csv())
+                .map_err(|error| format!("Could not write '{}': {}", path.display(), error)),
+            _ => save_png(&self.render(cell_size)?, path),
+        }
+    }
+}
+
+/// The colour of a heat between 0 and 1, going from dark red through yellow to white like the
+/// heatmap of the viewer
+pub fn heat_colour(heat: f64) -> Rgb<u8> {
+    let channel = |offset: f64| ((heat * 3.0 - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([channel(0.0), channel(1.0), channel(2.0)])
+}
+
+fn board_region(board: &Board) -> BoundingBox {
+    BoundingBox { x: 0, y: 0, width: board.width() as u64, height: board.height() as u64 }
+}
+
+fn add_counts(counts: &mut [u32], cells: &[u64], amount: i32) {
+    for (word_index, &word) in cells.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            let index = word_index * 64 + word.trailing_zeros() as usize;
+            counts[index] = counts[index].wrapping_add_signed(amount);
+            word &= word - 1;
+        }
+    }
+}
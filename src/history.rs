@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::mem;
+
+use game_of_life::Board;
+
+enum Edit {
+    // Cells that were changed by a brush stroke, with the value they were changed to
+    Cells(Vec<(usize, usize, bool)>),
+    // The board as it was before it was replaced, swapped with the current board on undo and redo
+    Board(Box<Board>),
+}
+
+impl Edit {
+    fn size(&self) -> usize {
+        match self {
+            Edit::Cells(cells) => cells.len() * mem::size_of::<(usize, usize, bool)>(),
+            Edit::Board(board) => board_size(board),
+        }
+    }
+
+    // Applies the edit to the board and returns the edit that reverts it
+    fn apply(self, board: &mut Board, undo: bool) -> Edit {
+        match self {
+            Edit::Cells(cells) => {
+                for &(x, y, value) in &cells {
+                    board.set_cell(x, y, value != undo);
+                }
+                Edit::Cells(cells)
+            }
+            Edit::Board(mut other) => {
+                mem::swap(board, &mut other);
+                Edit::Board(other)
+            }
+        }
+    }
+}
+
+/// Undo and redo history of brush strokes and board resets, dropping the oldest edits once the
+/// edits take up more memory than the budget
+pub struct EditHistory {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    stroke: Vec<(usize, usize, bool)>,
+    budget: usize,
+}
+
+impl EditHistory {
+    pub fn new(budget: usize) -> Self {
+        EditHistory {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            stroke: Vec::new(),
+            budget,
+        }
+    }
+
+    /// Sets a cell as part of the current brush stroke, returns whether the cell changed
+    pub fn paint(&mut self, board: &mut Board, x: usize, y: usize, value: bool) -> bool {
+        if x >= board.width() || y >= board.height() || board.is_cell_occupied(x, y) == value {
+            return false;
+        }
+        board.set_cell(x, y, value);
+        self.stroke.push((x, y, value));
+        true
+    }
+
+    pub fn end_stroke(&mut self) {
+        if !self.stroke.is_empty() {
+            let stroke = mem::take(&mut self.stroke);
+            self.push(Edit::Cells(stroke));
+        }
+    }
+
+    /// Records the board before it is cleared or replaced
+    pub fn record_board(&mut self, board: &Board) {
+        self.end_stroke();
+        self.push(Edit::Board(Box::new(board.clone())));
+    }
+
+    /// Records the board before it is replaced by another generation. Brush strokes are undone cell
+    /// by cell, so they may only be undone on the generation they were painted on, which the
+    /// recorded board is restored to first
+    pub fn seal(&mut self, board: &Board) {
+        self.end_stroke();
+        match self.undo.back() {
+            Some(Edit::Cells(_)) => self.push(Edit::Board(Box::new(board.clone()))),
+            _ => self.redo.clear(),
+        }
+    }
+
+    pub fn undo(&mut self, board: &mut Board) {
+        self.end_stroke();
+        if let Some(edit) = self.undo.pop_back() {
+            self.redo.push(edit.apply(board, true));
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) {
+        self.end_stroke();
+        if let Some(edit) = self.redo.pop() {
+            self.undo.push_back(edit.apply(board, false));
+            self.trim();
+        }
+    }
+
+    fn push(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        let mut used: usize = self.undo.iter().chain(self.redo.iter()).map(Edit::size).sum();
+        while used > self.budget {
+            match self.undo.pop_front() {
+                Some(edit) => used -= edit.size(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Past generations of the board, so a paused simulation can be stepped backwards. Generations
+/// that were stepped back over are kept to be replayed until the board is changed in another way
+pub struct GenerationHistory {
+    past: VecDeque<Board>,
+    future: Vec<Board>,
+    budget: usize,
+}
+
+impl GenerationHistory {
+    pub fn new(budget: usize) -> Self {
+        GenerationHistory {
+            past: VecDeque::new(),
+            future: Vec::new(),
+            budget,
+        }
+    }
+
+    /// Records the board before it is replaced by its next generation
+    pub fn push(&mut self, board: Board) {
+        self.future.clear();
+        self.past.push_back(board);
+        self.trim();
+    }
+
+    /// Replaces the board with the generation after it if it was stepped back over earlier
+    pub fn forward(&mut self, board: &mut Board) -> bool {
+        match self.future.pop() {
+            Some(next) => {
+                self.past.push_back(mem::replace(board, next));
+                self.trim();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn back(&mut self, board: &mut Board) -> bool {
+        match self.past.pop_back() {
+            Some(previous) => {
+                self.future.push(mem::replace(board, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn rewind(&mut self, board: &mut Board) {
+        while self.back(board) {}
+    }
+
+    pub fn fast_forward(&mut self, board: &mut Board) {
+        while self.forward(board) {}
+    }
+
+    /// Steps back or forward to the given generation, or as close to it as the history goes
+    pub fn seek(&mut self, board: &mut Board, generation: i128) {
+        while board.generation() > generation && self.back(board) {}
+        while board.generation() < generation && self.forward(board) {}
+    }
+
+    /// Forgets the generations that were stepped back over, once the board no longer leads to them
+    pub fn clear_future(&mut self) {
+        self.future.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.past.clear();
+        self.future.clear();
+    }
+
+    /// The oldest and newest generation that can be stepped to
+    pub fn range(&self, board: &Board) -> (i128, i128) {
+        let oldest = self.past.front().map_or(board.generation(), |oldest| oldest.generation());
+        let newest = self.future.first().map_or(board.generation(), |newest| newest.generation());
+        (oldest, newest)
+    }
+
+    fn trim(&mut self) {
+        let mut used: usize = self.past.iter().chain(self.future.iter()).map(board_size).sum();
+        while used > self.budget {
+            match self.past.pop_front() {
+                Some(board) => used -= board_size(&board),
+                None => break,
+            }
+        }
+    }
+}
+
+fn board_size(board: &Board) -> usize {
+    board.height() * board.words_per_row() * mem::size_of::<u64>()
+}
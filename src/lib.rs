@@ -0,0 +1,51 @@
+//! The simulation engine of the Game of Life viewer, usable without the window and renderer.
+//!
+//! A `Board` is stepped one generation at a time with `perform_generation` and a `Rule`. The
+//! `Simulation` trait offers the same operations for the `GridSimulation` of a board, the
+//! unbounded `SparseBoard` and the `HashLife` engine. A `CycleDetector` reports when a stepped
+//! board or simulation settles into a still life, oscillator or spaceship, which an `AutoPause`
+//! policy can use to decide when to stop. A `Census` names and counts the objects on a board, for
+//! instance after it was filled with a seeded random `Soup`. A `Snapshot` draws a board to an image
+//! in the colours of the viewer, a `Recorder` turns a run of generations into an animation.
+//! `CellAges` keeps track of how long cells have been alive or dead, for colouring them by age, and a
+//! `Heatmap` of how often they changed over the last generations. A `Theme` names the `Palette` of
+//! colours the viewer and the images use.
+
+pub mod ages;
+pub mod autopause;
+pub mod board;
+pub mod census;
+pub mod cycle;
+pub mod direction;
+pub mod hashlife;
+pub mod heatmap;
+pub mod life;
+pub mod pattern;
+pub mod plaintext;
+pub mod recording;
+pub mod render;
+pub mod rle;
+pub mod rule;
+pub mod simulation;
+pub mod soup;
+pub mod sparse;
+pub mod theme;
+pub mod topology;
+mod packed;
+
+pub use ages::{AgeColours, CellAges, Colouring};
+pub use autopause::{AutoPause, StopReason};
+pub use board::Board;
+pub use census::{Census, CensusEntry, KnownObjects};
+pub use cycle::{Cycle, CycleDetector};
+pub use hashlife::HashLife;
+pub use heatmap::{Heatmap, HeatmapMeasure};
+pub use pattern::{Pattern, PatternFile};
+pub use recording::Recorder;
+pub use render::{Palette, Snapshot};
+pub use rule::Rule;
+pub use simulation::{BoundingBox, Engine, GridSimulation, perform_generation, Simulation};
+pub use soup::{Soup, Symmetry};
+pub use sparse::SparseBoard;
+pub use theme::Theme;
+pub use topology::Topology;
@@ -0,0 +1,193 @@
+use crate::pattern::{grid_from_cells, PatternFile};
+use crate::rule::Rule;
+
+/// Parses a pattern in either the Life 1.05 or the Life 1.06 format, depending on its header
+pub fn parse(input: &str) -> Result<PatternFile, String> {
+    match input.lines().next().map(|line| line.trim()) {
+        Some("#Life 1.05") => parse_105(input),
+        Some("#Life 1.06") => parse_106(input),
+        _ => Err("Missing #Life 1.05 or #Life 1.06 header".to_string()),
+    }
+}
+
+/// Parses the Life 1.05 format: blocks of `.` and `*` rows, each positioned by a `#P x y` line
+pub fn parse_105(input: &str) -> Result<PatternFile, String> {
+    let mut pattern = PatternFile::default();
+    let mut cells = Vec::new();
+    let mut block = (0i64, 0i64);
+    let mut row = 0i64;
+
+    for line in input.lines().skip(1) {
+        let line = line.trim();
+
+        if let Some(line) = line.strip_prefix('#') {
+            let mut chars = line.chars();
+            let tag = chars.next();
+            let text = chars.as_str().trim();
+
+            match tag {
+                Some('D') => pattern.comments.push(text.to_string()),
+                Some('N') => pattern.rule = Some(Rule::conway()),
+                Some('R') => pattern.rule = Some(Rule::parse(text)?),
+                Some('P') => {
+                    block = parse_coordinate(text)?;
+                    row = 0;
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        for (column, cell) in line.chars().enumerate() {
+            match cell {
+                '.' => (),
+                '*' => {
+                    let cell = block.0.checked_add(column as i64).zip(block.1.checked_add(row))
+                        .ok_or_else(|| format!("Cell in the block at #P {} {} is out of range", block.0, block.1))?;
+                    cells.push(cell);
+                }
+                _ => return Err(format!("Unexpected character '{}' in Life 1.05 pattern", cell)),
+            }
+        }
+        row += 1;
+    }
+
+    pattern.grid = grid_from_cells(&cells)?;
+    Ok(pattern)
+}
+
+/// Parses the Life 1.06 format: one `x y` coordinate per alive cell
+pub fn parse_106(input: &str) -> Result<PatternFile, String> {
+    let mut pattern = PatternFile::default();
+    let mut cells = Vec::new();
+
+    for line in input.lines().skip(1) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        cells.push(parse_coordinate(line)?);
+    }
+
+    pattern.grid = grid_from_cells(&cells)?;
+    Ok(pattern)
+}
+
+fn parse_coordinate(text: &str) -> Result<(i64, i64), String> {
+    let invalid = || format!("Invalid coordinate '{}'", text);
+    let mut parts = text.split_whitespace().map(|part| part.parse::<i64>());
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Writes a pattern in the Life 1.05 format as a single block centered on the origin
+pub fn write_105(pattern: &PatternFile) -> String {
+    let mut output = String::from("#Life 1.05\n");
+
+    if let Some(name) = &pattern.name {
+        output.push_str(&format!("#D {}\n", name));
+    }
+    for comment in &pattern.comments {
+        output.push_str(&format!("#D {}\n", comment));
+    }
+
+    match pattern.rule {
+        None => output.push_str("#N\n"),
+        Some(rule) if rule == Rule::conway() => output.push_str("#N\n"),
+        Some(rule) => output.push_str(&format!("#R {}\n", rule.to_survival_birth_string())),
+    }
+
+    let (x, y) = origin_offset(pattern);
+    output.push_str(&format!("#P {} {}\n", x, y));
+
+    for row in &pattern.grid {
+        let line: String = row.iter().map(|&cell| if cell == 1 { '*' } else { '.' }).collect();
+        let line = line.trim_end_matches('.');
+        // Empty rows still need a character to not be mistaken for the end of the block
+        output.push_str(if line.is_empty() { "." } else { line });
+        output.push('\n');
+    }
+    output
+}
+
+/// Writes a pattern in the Life 1.06 format, with coordinates relative to the pattern's center
+pub fn write_106(pattern: &PatternFile) -> String {
+    let mut output = String::from("#Life 1.06\n");
+    let (offset_x, offset_y) = origin_offset(pattern);
+
+    for (y, row) in pattern.grid.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            if cell == 1 {
+                output.push_str(&format!("{} {}\n", x as i64 + offset_x, y as i64 + offset_y));
+            }
+        }
+    }
+    output
+}
+
+fn origin_offset(pattern: &PatternFile) -> (i64, i64) {
+    let height = pattern.grid.len() as i64;
+    let width = pattern.grid.iter().map(|row| row.len()).max().unwrap_or(0) as i64;
+    (-(width / 2), -(height / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A grid with live cells on every edge, which the formats keep as is
+    fn glider_gun() -> Vec<Vec<u8>> {
+        crate::rle::parse("x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\
+                           obo$10bo5bo7bo$11bo3bo$12b2o!").unwrap().grid
+    }
+
+    #[test]
+    fn round_trips_life_105() {
+        let pattern = PatternFile {
+            name: None,
+            comments: vec!["Gosper glider gun".to_string()],
+            rule: Some(Rule::parse("B36/S23").unwrap()),
+            grid: glider_gun(),
+        };
+        assert_eq!(parse(&write_105(&pattern)).unwrap(), pattern);
+    }
+
+    #[test]
+    fn writes_conway_as_the_normal_rule() {
+        let pattern = PatternFile { rule: Some(Rule::conway()), grid: glider_gun(), ..PatternFile::default() };
+        let written = write_105(&pattern);
+        assert!(written.contains("\n#N\n"));
+        assert_eq!(parse(&written).unwrap(), pattern);
+    }
+
+    #[test]
+    fn round_trips_life_106() {
+        let pattern = PatternFile { grid: glider_gun(), ..PatternFile::default() };
+        assert_eq!(parse(&write_106(&pattern)).unwrap(), pattern);
+    }
+
+    #[test]
+    fn places_blocks_of_life_105() {
+        let pattern = parse_105("#Life 1.05\n#P -1 -1\n*.*\n#P 3 1\n**\n").unwrap();
+        assert_eq!(pattern.grid, vec![vec![1, 0, 1, 0, 0, 0], vec![0; 6], vec![0, 0, 0, 0, 1, 1]]);
+    }
+
+    #[test]
+    fn rejects_invalid_coordinates() {
+        assert!(parse("#Life 1.06\n0 0\n1 x\n").is_err());
+        assert!(parse("0 0\n").is_err());
+    }
+
+    #[test]
+    fn rejects_patterns_beyond_the_size_limit() {
+        assert!(parse("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n").is_err());
+        assert!(parse("#Life 1.06\n0 0\n1000000 1000000\n").is_err());
+        assert!(parse("#Life 1.06\n0 0\n16384 0\n").is_err());
+        assert_eq!(parse("#Life 1.06\n0 0\n16383 0\n").unwrap().grid[0].len(), 16384);
+        assert!(parse("#Life 1.05\n#P 9223372036854775807 0\n.*\n").is_err());
+        assert!(parse("#Life 1.05\n#P 0 9223372036854775807\n*\n*\n").is_err());
+    }
+}
@@ -2,6 +2,7 @@
 #[macro_use]
 extern crate glium;
 
+use std::path::PathBuf;
 use std::time::Instant;
 
 use glium::{Display, Program, Surface, Texture2d, VertexBuffer};
@@ -9,50 +10,108 @@ use glium::glutin::surface::WindowSurface;
 use glium::index::NoIndices;
 use glium::index::PrimitiveType::TrianglesList;
 use glium::texture::RawImage2d;
-use glium::uniforms::{EmptyUniforms, MagnifySamplerFilter, MinifySamplerFilter, Sampler, UniformsStorage};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Uniforms};
+use image::Rgb;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use winit::dpi::LogicalSize;
-use winit::event::{ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+use winit::event::{ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode,
+                   WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
 use winit::window::{Icon, Window, WindowButtons};
 
-use board::Board;
+use game_of_life::{AgeColours, Board, CellAges, Census, Colouring, Cycle, CycleDetector, Engine, Heatmap,
+                   KnownObjects, perform_generation, Recorder, Rule, StopReason};
+use game_of_life::cycle::DEFAULT_MAX_PERIOD;
+use game_of_life::direction::Direction;
+use game_of_life::direction::Direction::East;
+use game_of_life::pattern::{Pattern, PatternFile};
+use game_of_life::render::{save_png, Palette, Snapshot};
 use shader::create_shader_program;
 
-use crate::board::BOARD_SIZE;
-use crate::direction::Direction;
-use crate::direction::Direction::East;
-use crate::pattern::Pattern;
+use crate::camera::Camera;
+use crate::history::{EditHistory, GenerationHistory};
+use crate::options::Options;
 use crate::vertex::Vertex;
 
-mod board;
 mod vertex;
 mod shader;
-mod pattern;
-mod direction;
+mod history;
+mod camera;
+mod options;
 
 const LOGIC_UPDATE_TIME: f32 = 1000f32;
 const WINDOW_SIZE: i16 = 1024i16;
 
 fn main() {
-    let mut board = Board::new();
-    let center = BOARD_SIZE / 2;
+    let options = exit_on_error(Options::from_args());
 
-    board.place_rotated_pattern(Pattern::get_random_pattern(), center, center, East);
+    if options.headless || options.search.is_some() {
+        eprintln!("Headless mode and soup searches are run by the game-of-life-headless binary");
+        std::process::exit(1);
+    }
+    // The viewer shows a bounded board, unbounded planes are only simulated headless
+    if options.engine != Engine::Grid {
+        eprintln!("The {} engine is only available in headless mode", options.engine.key());
+        std::process::exit(1);
+    }
+
+    let mut rule = options.rule.unwrap_or_default();
+    let mut board = Board::with_size(options.width, options.height);
+    board.topology = options.topology;
+    let center = (board.width() / 2, board.height() / 2);
+
+    // Soups and random patterns are reproducible by passing the printed seed with --seed
+    let seed = options.seed.unwrap_or_else(rand::random);
+    println!("Random seed {}", seed);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut next_soup_seed = seed;
+    // Shown in the title as well, where it is seen on platforms without a console
+    let mut shown_seed = seed;
+
+    match &options.pattern {
+        Some(path) => {
+            let pattern = exit_on_error(PatternFile::load(path));
+            if let (None, Some(pattern_rule)) = (options.rule, pattern.rule) {
+                rule = pattern_rule;
+            }
+            board.set_cells(pattern.grid, center.0, center.1);
+        }
+        None if options.soup.is_some() => {
+            place_soup(&mut board, &options, next_soup_seed, center);
+            next_soup_seed = next_soup_seed.wrapping_add(1);
+        }
+        None => {
+            board.place_rotated_pattern(Pattern::get_random_pattern(&mut rng), center.0, center.1, East);
+        }
+    }
 
     let event_loop = EventLoopBuilder::new().build();
-    let (window, display) = create_window_display(&event_loop);
+    let logical_size = get_window_size(&board);
+    let (window, display) = create_window_display(&event_loop, logical_size);
     let scale_factor = window.scale_factor();
-    let window_size = (WINDOW_SIZE as f64 * scale_factor, WINDOW_SIZE as f64 * scale_factor);
+    let mut window_size = (logical_size.0 as f64 * scale_factor, logical_size.1 as f64 * scale_factor);
+    let mut camera = Camera::fit(window_size, (board.width(), board.height()));
     let program = create_shader_program(&display);
 
     // VBO to render a screen filling rectangle
     let vertex_buffer = create_rect_vbo(&display);
     let indices = NoIndices(TrianglesList);
 
-    let data = create_data_from_board(&board);
+    let mut theme = options.theme;
+    let mut snapshot = options.snapshot;
+    let mut axes = options.axes;
+    let mut age_colours = options.age_colours;
+    // Cells are only aged while they are coloured by age, ageing every cell slows down large boards
+    let mut ages = (age_colours.colouring != Colouring::Plain).then(CellAges::new);
+    let data = create_data_from_board(&board, ages.as_ref(), &age_colours);
 
-    let image = RawImage2d::from_raw_rgb(data, (BOARD_SIZE as u32, BOARD_SIZE as u32));
+    let image = RawImage2d::from_raw_rgb(data, (board.width() as u32, board.height() as u32));
     let texture = Texture2d::new(&display, image).unwrap();
+    let mut heatmap: Option<Heatmap> = None;
+    let heat_image = RawImage2d::from_raw_rgb(vec![0u8; board.width() * board.height() * 3],
+                                              (board.width() as u32, board.height() as u32));
+    let heat_texture = Texture2d::new(&display, heat_image).unwrap();
 
     let mut last_update_time = Instant::now();
     let mut elapsed: i128 = LOGIC_UPDATE_TIME as i128;
@@ -62,13 +121,36 @@ fn main() {
     let mut mouse_dragging = false;
     let mut mouse_erase = false;
     let mut mouse_position = (0f64, 0f64);
+    let mut mouse_panning = false;
+    let mut modifiers = ModifiersState::empty();
+    let mut history = EditHistory::new(options.undo_memory);
+    let mut generations = GenerationHistory::new(options.history_memory);
+    // Looks back far enough to find the periods to pause on
+    let max_period = options.auto_pause.max_period.map_or(0, |period| period as usize);
+    let mut cycles = CycleDetector::with_max_period(max_period.max(DEFAULT_MAX_PERIOD));
+    // Why the simulation paused by itself, kept after resuming so it doesn't pause again right away
+    let mut stop_reason: Option<StopReason> = None;
+    // Recording starts right away when a file to record to was given
+    let mut recording = options.record.as_ref().map(|_| start_recording(&board, &snapshot, &options));
 
     event_loop.run(move |event, _, control_flow| {
         match event {
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => {
+                    if let Some(recorder) = recording.take() {
+                        save_recording(&recorder, &options);
+                    }
                     *control_flow = ControlFlow::Exit;
                 }
+                WindowEvent::Resized(size) => {
+                    display.resize((size.width, size.height));
+                    let new_size = (size.width as f64, size.height as f64);
+                    camera.resize(window_size, new_size);
+                    window_size = new_size;
+                }
+                WindowEvent::ModifiersChanged(state) => {
+                    modifiers = state;
+                }
                 WindowEvent::KeyboardInput {
                     input: KeyboardInput {
                         state: ElementState::Pressed,
@@ -79,6 +161,9 @@ fn main() {
                 } => {
                     match key {
                         VirtualKeyCode::Escape => {
+                            if let Some(recorder) = recording.take() {
+                                save_recording(&recorder, &options);
+                            }
                             *control_flow = ControlFlow::Exit;
                         }
                         VirtualKeyCode::Space => {
@@ -86,19 +171,148 @@ fn main() {
                         }
                         VirtualKeyCode::R => {
                             running = false;
-                            board = Board::new();
-                            board.place_rotated_pattern(Pattern::get_random_pattern(), center,
-                                                        center, Direction::get_random_direction());
+                            history.record_board(&board);
+                            generations.clear();
+                            cycles.reset();
+                            stop_reason = None;
+                            board = board.cleared();
+                            board.place_rotated_pattern(Pattern::get_random_pattern(&mut rng), center.0,
+                                                        center.1, Direction::get_random_direction(&mut rng));
+                        }
+                        VirtualKeyCode::N => {
+                            running = false;
+                            history.record_board(&board);
+                            generations.clear();
+                            cycles.reset();
+                            stop_reason = None;
+                            board = board.cleared();
+                            place_soup(&mut board, &options, next_soup_seed, center);
+                            shown_seed = next_soup_seed;
+                            next_soup_seed = next_soup_seed.wrapping_add(1);
                         }
                         VirtualKeyCode::C => {
                             running = false;
-                            board = Board::new();
+                            history.record_board(&board);
+                            generations.clear();
+                            cycles.reset();
+                            stop_reason = None;
+                            board = board.cleared();
+                        }
+                        VirtualKeyCode::Z if modifiers.ctrl() || modifiers.logo() => {
+                            running = false;
+                            generations.clear();
+                            cycles.reset();
+                            stop_reason = None;
+                            if modifiers.shift() {
+                                history.redo(&mut board);
+                            } else {
+                                history.undo(&mut board);
+                            }
                         }
-                        VirtualKeyCode::D => {
+                        VirtualKeyCode::D | VirtualKeyCode::Right => {
                             if !running {
-                                board = perform_generation(&mut board);
+                                history.seal(&board);
+                                step_forward(&mut board, &rule, options.threads, &mut generations, &mut cycles,
+                                             &mut ages, &mut heatmap);
+                                if let Some(recorder) = &mut recording {
+                                    record_frame(recorder, &board, &options);
+                                }
+                            }
+                        }
+                        VirtualKeyCode::Left => {
+                            running = false;
+                            history.seal(&board);
+                            generations.back(&mut board);
+                            stop_reason = None;
+                        }
+                        VirtualKeyCode::Home => {
+                            running = false;
+                            history.seal(&board);
+                            generations.rewind(&mut board);
+                            stop_reason = None;
+                        }
+                        VirtualKeyCode::End => {
+                            running = false;
+                            history.seal(&board);
+                            generations.fast_forward(&mut board);
+                        }
+                        VirtualKeyCode::PageUp => {
+                            running = false;
+                            history.seal(&board);
+                            let target = board.generation() - options.seek_step as i128;
+                            generations.seek(&mut board, target);
+                            stop_reason = None;
+                        }
+                        VirtualKeyCode::PageDown => {
+                            running = false;
+                            history.seal(&board);
+                            let target = board.generation() + options.seek_step as i128;
+                            generations.seek(&mut board, target);
+                        }
+                        VirtualKeyCode::B => {
+                            rule = rule.next_named();
+                            generations.clear_future();
+                            cycles.reset();
+                            stop_reason = None;
+                        }
+                        VirtualKeyCode::T => {
+                            board.topology = board.topology.next();
+                            generations.clear_future();
+                            cycles.reset();
+                            stop_reason = None;
+                        }
+                        VirtualKeyCode::S => {
+                            save_board(&board, &rule);
+                        }
+                        VirtualKeyCode::P => {
+                            save_snapshot(&board, &snapshot);
+                        }
+                        VirtualKeyCode::V => {
+                            match recording.take() {
+                                Some(recorder) => save_recording(&recorder, &options),
+                                None => recording = Some(start_recording(&board, &snapshot, &options)),
                             }
                         }
+                        VirtualKeyCode::A => {
+                            age_colours.colouring = age_colours.colouring.next();
+                            ages = match age_colours.colouring {
+                                Colouring::Plain => None,
+                                _ => ages.take().or_else(|| Some(CellAges::new())),
+                            };
+                        }
+                        VirtualKeyCode::H if modifiers.shift() => {
+                            match &heatmap {
+                                Some(heatmap) => save_heatmap(heatmap, &board, &options),
+                                None => eprintln!("Turn on the heatmap with H before saving it"),
+                            }
+                        }
+                        VirtualKeyCode::H => {
+                            heatmap = match heatmap {
+                                Some(_) => None,
+                                None => Some(Heatmap::of_board(options.heatmap_measure, options.heatmap_window,
+                                                               &board)),
+                            };
+                        }
+                        VirtualKeyCode::Tab => {
+                            theme = (theme + 1) % options.themes.len();
+                            snapshot.palette = options.themes[theme].palette;
+                            if let Some(recorder) = &mut recording {
+                                recorder.snapshot.palette = snapshot.palette;
+                            }
+                        }
+                        VirtualKeyCode::G => {
+                            snapshot.grid = !snapshot.grid;
+                        }
+                        VirtualKeyCode::X => {
+                            axes = !axes;
+                        }
+                        VirtualKeyCode::F => {
+                            camera = Camera::fit(window_size, (board.width(), board.height()));
+                        }
+                        VirtualKeyCode::O => {
+                            let census = Census::of_board(&board, &KnownObjects::new(&rule));
+                            print!("Census of generation {}\n{}", board.generation(), census);
+                        }
                         VirtualKeyCode::Key1 => {
                             speed = 1f32;
                         }
@@ -127,32 +341,51 @@ fn main() {
                         (MouseButton::Left, ElementState::Pressed) => {
                             mouse_dragging = true;
                             mouse_erase = false;
-                            if !running {
-                                set_cell_at_cursor(window_size, mouse_position, &mut board,
-                                                   !mouse_erase);
+                            // Only a change to the board starts the detection over
+                            if !running && set_cell_at_cursor(&camera, mouse_position, &mut board, &mut history,
+                                                              !mouse_erase) {
+                                generations.clear_future();
+                                cycles.reset();
+                                stop_reason = None;
                             }
                         }
                         (MouseButton::Right, ElementState::Pressed) => {
                             mouse_dragging = true;
                             mouse_erase = true;
-                            if !running {
-                                set_cell_at_cursor(window_size, mouse_position, &mut board,
-                                                   !mouse_erase);
+                            // Only a change to the board starts the detection over
+                            if !running && set_cell_at_cursor(&camera, mouse_position, &mut board, &mut history,
+                                                              !mouse_erase) {
+                                generations.clear_future();
+                                cycles.reset();
+                                stop_reason = None;
                             }
                         }
+                        (MouseButton::Middle, state) => {
+                            mouse_panning = state == ElementState::Pressed;
+                        }
                         (MouseButton::Left | MouseButton::Right, ElementState::Released) => {
                             mouse_dragging = false;
+                            history.end_stroke();
                         }
                         _ => ()
                     }
                 }
                 WindowEvent::CursorMoved { position, .. } => {
+                    if mouse_panning {
+                        camera.pan((position.x - mouse_position.0, position.y - mouse_position.1));
+                    }
                     mouse_position = (position.x, position.y);
-                    if mouse_dragging {
-                        if !running {
-                            set_cell_at_cursor(window_size, mouse_position, &mut board,
-                                               !mouse_erase);
-                        }
+                    if mouse_dragging && !running && set_cell_at_cursor(&camera, mouse_position, &mut board,
+                                                                         &mut history, !mouse_erase) {
+                        generations.clear_future();
+                        cycles.reset();
+                        stop_reason = None;
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    match delta {
+                        MouseScrollDelta::LineDelta(_, lines) => camera.zoom_at(mouse_position, lines as f64),
+                        MouseScrollDelta::PixelDelta(pixels) => camera.zoom_at_pixels(mouse_position, pixels.y),
                     }
                 }
                 _ => ()
@@ -172,26 +405,73 @@ fn main() {
                 let sampler = texture.sampled()
                     .magnify_filter(MagnifySamplerFilter::Nearest)
                     .minify_filter(MinifySamplerFilter::Nearest);
+                let heat_sampler = heat_texture.sampled()
+                    .magnify_filter(MagnifySamplerFilter::Nearest)
+                    .minify_filter(MinifySamplerFilter::Nearest);
 
                 let uniforms = uniform! {
                     screensize: [window_size.0 as f32, window_size.1 as f32],
-                    boardsize: [BOARD_SIZE as f32, BOARD_SIZE as f32],
+                    boardsize: [board.width() as f32, board.height() as f32],
+                    offset: [camera.offset.0 as f32, camera.offset.1 as f32],
+                    zoom: camera.zoom as f32,
+                    showgrid: snapshot.grid,
+                    majorgrid: options.major_grid.unwrap_or(0) as f32,
+                    axes: axes,
+                    origin: [center.0 as f32, center.1 as f32],
+                    colouring: age_colours.colouring as i32,
+                    newborn: colour_to_vec(age_colours.newborn),
+                    old: colour_to_vec(age_colours.old),
+                    trail: colour_to_vec(age_colours.trail),
                     tex: sampler,
+                    heatmap: heatmap.is_some(),
+                    heat: heat_sampler,
+                    foreground: colour_to_vec(snapshot.palette.foreground),
+                    background: colour_to_vec(snapshot.palette.background),
+                    grid: colour_to_vec(snapshot.palette.grid),
+                    outside: colour_to_vec(snapshot.palette.outside),
                 };
 
                 let logic_time: i128 = (LOGIC_UPDATE_TIME * speed).round() as i128;
 
                 while elapsed > logic_time {
                     if running {
-                        board = perform_generation(&mut board);
+                        history.seal(&board);
+                        step_forward(&mut board, &rule, options.threads, &mut generations, &mut cycles,
+                                     &mut ages, &mut heatmap);
+                        if let Some(recorder) = &mut recording {
+                            record_frame(recorder, &board, &options);
+                        }
+
+                        if stop_reason.is_none() {
+                            stop_reason = options.auto_pause.check(board.generation(), board.population(),
+                                                                   cycles.cycle());
+                            if let Some(reason) = stop_reason {
+                                println!("Paused, the board {}", reason);
+                                running = false;
+                            }
+                        }
                     }
                     elapsed -= logic_time;
                 }
-                update_texture(&texture, &board);
-                draw_frame(&display, &program, &vertex_buffer, &indices, &uniforms);
-                let title = format!("Game of Life :: Speed {} :: Generation {} :: Population {} {}",
-                                    speed_to_string(speed), board.generation, board.population,
-                                    sim_state_to_string(running));
+                // Picks up the board after it was changed other than by stepping it
+                cycles.observe(&board);
+                // Picks up cells drawn or erased since the last step
+                if let Some(ages) = &mut ages {
+                    ages.observe(&board);
+                }
+                update_texture(&texture, &board, ages.as_ref(), &age_colours);
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.observe(&board);
+                    update_heat_texture(&heat_texture, heatmap);
+                }
+                draw_frame(&display, &program, &vertex_buffer, &indices, &uniforms, &snapshot.palette);
+                let title = format!("Game of Life :: {} :: {} :: Speed {} :: Generation {} {} :: Population {} {} {} \
+                                     {} {} :: Seed {}",
+                                    rule_to_string(&rule), board.topology.name(), speed_to_string(speed),
+                                    board.generation(), history_to_string(generations.range(&board)),
+                                    board.population(), cycle_to_string(cycles.cycle()),
+                                    sim_state_to_string(running, stop_reason), recording_to_string(&recording),
+                                    heatmap_to_string(&heatmap), shown_seed);
                 window.set_title(&title);
             }
             _ => (),
@@ -199,11 +479,146 @@ fn main() {
     });
 }
 
-fn sim_state_to_string(running: bool) -> String {
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn place_soup(board: &mut Board, options: &Options, seed: u64, center: (usize, usize)) {
+    board.set_cells(exit_on_error(options.soup(seed).grid()), center.0, center.1);
+    println!("Placed soup with seed {}", seed);
+}
+
+fn save_snapshot(board: &Board, snapshot: &Snapshot) {
+    let path = PathBuf::from(format!("board-{}.png", board.generation()));
+    match snapshot.render_board(board).and_then(|image| save_png(&image, &path)) {
+        Ok(()) => println!("Saved board to {}", path.display()),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+fn start_recording(board: &Board, snapshot: &Snapshot, options: &Options) -> Recorder {
+    let mut recorder = Recorder::new(*snapshot, options.record_every, options.frame_delay);
+    record_frame(&mut recorder, board, options);
+    println!("Recording from generation {}", board.generation());
+    recorder
+}
+
+// Only the generations between --record-from and --record-to are recorded
+fn record_frame(recorder: &mut Recorder, board: &Board, options: &Options) {
+    let generation = board.generation() as u64;
+    if options.record_from.is_none_or(|from| generation >= from)
+        && options.record_to.is_none_or(|to| generation <= to) {
+        recorder.capture_board(board);
+    }
+}
+
+fn save_recording(recorder: &Recorder, options: &Options) {
+    let path = options.record.clone().unwrap_or_else(|| {
+        PathBuf::from(format!("recording-{}.gif", recorder.first_generation().unwrap_or_default()))
+    });
+    match recorder.save(&path) {
+        Ok(()) => println!("Saved {} frames to {}", recorder.frame_count(), path.display()),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+fn save_heatmap(heatmap: &Heatmap, board: &Board, options: &Options) {
+    for extension in ["png", "csv"] {
+        let path = PathBuf::from(format!("heatmap-{}.{}", board.generation(), extension));
+        match heatmap.save(&path, options.snapshot.cell_size) {
+            Ok(()) => println!("Saved heatmap of {} generations to {}", heatmap.generations(), path.display()),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+}
+
+fn save_board(board: &Board, rule: &Rule) {
+    let pattern = PatternFile {
+        name: None,
+        comments: vec![format!("Generation {}", board.generation())],
+        rule: Some(*rule),
+        grid: board.to_grid(),
+    };
+
+    let path = PathBuf::from(format!("board-{}.rle", board.generation()));
+    match pattern.save(&path) {
+        Ok(()) => println!("Saved board to {}", path.display()),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+// Replays a generation that was stepped back over, or computes the next one
+fn step_forward(
+    board: &mut Board,
+    rule: &Rule,
+    threads: usize,
+    generations: &mut GenerationHistory,
+    cycles: &mut CycleDetector,
+    ages: &mut Option<CellAges>,
+    heatmap: &mut Option<Heatmap>) {
+    if !generations.forward(board) {
+        let next = perform_generation(board, rule, threads);
+        generations.push(std::mem::replace(board, next));
+    }
+    cycles.observe(board);
+    if let Some(ages) = ages {
+        ages.observe(board);
+    }
+    if let Some(heatmap) = heatmap {
+        heatmap.observe(board);
+    }
+}
+
+fn history_to_string((oldest, newest): (i128, i128)) -> String {
+    if oldest == newest {
+        return "".to_string();
+    }
+    format!("({}-{})", oldest, newest)
+}
+
+fn cycle_to_string(cycle: Option<Cycle>) -> String {
+    match cycle {
+        Some(cycle) => format!(":: {}", cycle),
+        None => "".to_string(),
+    }
+}
+
+fn sim_state_to_string(running: bool, stop_reason: Option<StopReason>) -> String {
     if running {
         return "".to_string();
     }
-    return ":: Paused".to_string();
+    match stop_reason {
+        Some(reason) => format!(":: Paused, the board {}", reason),
+        None => ":: Paused".to_string(),
+    }
+}
+
+fn recording_to_string(recording: &Option<Recorder>) -> String {
+    match recording {
+        Some(recorder) => format!(":: Recording {} frames", recorder.frame_count()),
+        None => "".to_string(),
+    }
+}
+
+fn heatmap_to_string(heatmap: &Option<Heatmap>) -> String {
+    match heatmap {
+        Some(heatmap) => format!(":: Heatmap of {} over {} generations", heatmap.measure.name().to_lowercase(),
+                                 heatmap.generations()),
+        None => "".to_string(),
+    }
+}
+
+fn rule_to_string(rule: &Rule) -> String {
+    match rule.name() {
+        Some(name) => format!("{} ({})", name, rule),
+        None => rule.to_string(),
+    }
 }
 
 fn speed_to_string(speed: f32) -> String {
@@ -215,85 +630,94 @@ fn speed_to_string(speed: f32) -> String {
 }
 
 fn set_cell_at_cursor(
-    window_size: (f64, f64),
+    camera: &Camera,
     mouse_position: (f64, f64),
-    board: &mut Board, draw: bool) {
-    let (x, y) = mouse_position;
+    board: &mut Board, history: &mut EditHistory, draw: bool) -> bool {
+    let (x, y) = camera.screen_to_board((mouse_position.0.floor(), mouse_position.1.floor()));
 
-    if x < 0f64 || y < 0f64 || x > window_size.0 || y > window_size.1 {
-        return;
+    if x < 0f64 || y < 0f64 || x >= board.width() as f64 || y >= board.height() as f64 {
+        return false;
     }
 
-    let cell_size = window_size.0 / (BOARD_SIZE as f64);
-    let x = (x.floor() / cell_size as f64).floor() as usize;
-    let y = (y.floor() / cell_size as f64).floor() as usize;
-
-    board.set_cell(x, y, draw);
+    history.paint(board, x.floor() as usize, y.floor() as usize, draw)
 }
 
 
-fn perform_generation(board: &mut Board) -> Board {
-    let mut new_board = Board::new();
-
-    for (y, row) in board.cells.iter().enumerate() {
-        for (x, _) in row.iter().enumerate() {
-            new_board.set_cell(x, y, update_cell(board, x, y));
-        }
+// The red channel marks live cells, green is the age of live cells from newborn at 0 to old at 255
+// and blue how much is left of the trail of dead cells
+fn create_data_from_board(board: &Board, ages: Option<&CellAges>, age_colours: &AgeColours) -> Vec<u8> {
+    let mut data = vec![0u8; board.width() * board.height() * 3];
+    for (x, y) in board.live_cells() {
+        data[(y * board.width() + x) * 3] = 255;
     }
-    new_board.generation = board.generation + 1;
-    return new_board;
-}
-
-fn update_cell(board: &Board, x: usize, y: usize) -> bool {
-    let active = board.is_cell_occupied(x, y);
-    let neighbours = board.get_active_neighbours(x as i32, y as i32);
 
-    if active {
-        if neighbours != 2 && neighbours != 3 {
-            return false;
+    match (age_colours.colouring, ages) {
+        (Colouring::Plain, _) | (_, None) => (),
+        (Colouring::Age | Colouring::Trails, Some(ages)) => {
+            let max_age = age_colours.max_age.max(1);
+            let trail_length = age_colours.trail_length.max(1);
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    let index = (y * board.width() + x) * 3;
+                    let age = ages.age(x, y);
+                    if age > 0 {
+                        data[index + 1] = ((age - 1).min(max_age) * 255 / max_age) as u8;
+                    } else if let Some(dead_for) = ages.dead_for(x, y) {
+                        let left = trail_length.saturating_sub(dead_for - 1);
+                        data[index + 2] = (left * 255 / trail_length) as u8;
+                    }
+                }
+            }
         }
-        return true;
     }
 
-    if neighbours == 3 {
-        return true;
-    }
-    return false;
+    return data;
 }
 
-fn create_data_from_board(board: &Board) -> Vec<u8> {
-    let mut data = Vec::new();
-    for row in board.cells.iter() {
-        for cell in row.iter() {
-            if *cell {
-                data.extend_from_slice(&[255u8, 0, 0]);
-            } else {
-                data.extend_from_slice(&[0u8, 0, 0]);
-            }
-        }
-    }
+fn update_texture(texture: &Texture2d, board: &Board, ages: Option<&CellAges>, age_colours: &AgeColours) {
+    let width: u32 = board.width() as u32;
+    let height: u32 = board.height() as u32;
 
-    return data;
+    texture.main_level().write(glium::Rect {
+        left: 0,
+        bottom: 0,
+        width,
+        height,
+    }, RawImage2d::from_raw_rgb(create_data_from_board(board, ages, age_colours), (width, height)));
 }
 
-fn update_texture(texture: &Texture2d, board: &Board) {
-    let width: u32 = BOARD_SIZE as u32;
-    let height: u32 = BOARD_SIZE as u32;
+// The red channel is the count of each cell relative to the highest count
+fn update_heat_texture(texture: &Texture2d, heatmap: &Heatmap) {
+    let region = heatmap.region();
+    let (width, height) = (region.width as u32, region.height as u32);
+    let max = heatmap.max().max(1) as u64;
+
+    let mut data = vec![0u8; (width * height * 3) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let count = heatmap.count(x as i64, y as i64) as u64;
+            data[((y * width + x) * 3) as usize] = (count * 255).div_ceil(max) as u8;
+        }
+    }
 
     texture.main_level().write(glium::Rect {
         left: 0,
         bottom: 0,
         width,
         height,
-    }, RawImage2d::from_raw_rgb(create_data_from_board(board), (width, height)));
+    }, RawImage2d::from_raw_rgb(data, (width, height)));
+}
+
+fn colour_to_vec(colour: Rgb<u8>) -> [f32; 3] {
+    [colour[0] as f32 / 255.0, colour[1] as f32 / 255.0, colour[2] as f32 / 255.0]
 }
 
 fn draw_frame(display: &Display<WindowSurface>, program: &Program, vertex_buffer: &VertexBuffer<Vertex>,
-              indices: &NoIndices, uniforms: &UniformsStorage<Sampler<Texture2d>,
-        UniformsStorage<[f32; 2], UniformsStorage<[f32; 2], EmptyUniforms>>>) {
+              indices: &NoIndices, uniforms: &impl Uniforms, palette: &Palette) {
     let mut target = display.draw();
 
-    target.clear_color(0.0, 0.0, 1.0, 1.0);
+    let [red, green, blue] = colour_to_vec(palette.outside);
+    target.clear_color(red, green, blue, 1.0);
     target.draw(vertex_buffer, indices, &program, uniforms,
                 &Default::default()).unwrap();
     target.finish().unwrap();
@@ -314,16 +738,24 @@ fn get_rect_vertices() -> Vec<Vertex> {
     ]
 }
 
-fn create_window_display(event_loop: &EventLoop<()>) -> (Window, Display<WindowSurface>) {
+// Fit the board in a window of WINDOW_SIZE along its longest side, keeping the cells square
+fn get_window_size(board: &Board) -> (u32, u32) {
+    let longest = board.width().max(board.height()) as f64;
+    let width = (WINDOW_SIZE as f64 * board.width() as f64 / longest).round().max(1.0);
+    let height = (WINDOW_SIZE as f64 * board.height() as f64 / longest).round().max(1.0);
+    (width as u32, height as u32)
+}
+
+fn create_window_display(event_loop: &EventLoop<()>, size: (u32, u32)) -> (Window, Display<WindowSurface>) {
     let image = image::load(std::io::Cursor::new(&include_bytes!("../assets/icon.png")),
                             image::ImageFormat::Png).unwrap().to_rgba8();
     let icon = Icon::from_rgba(image.as_raw().to_owned(), image.width(), image.height()).unwrap();
 
     let window_builder = winit::window::WindowBuilder::new()
-        .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE)
-        .with_inner_size(LogicalSize::new(1024, 1024))
+        .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE | WindowButtons::MAXIMIZE)
+        .with_inner_size(LogicalSize::new(size.0, size.1))
         .with_window_icon(Some(icon))
-        .with_resizable(false);
+        .with_resizable(true);
 
 
     glium::backend::glutin::SimpleWindowBuilder::new()
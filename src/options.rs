@@ -0,0 +1,336 @@
+use std::path::PathBuf;
+use std::thread;
+
+use game_of_life::ages::{AgeColours, Colouring};
+use game_of_life::autopause::AutoPause;
+use game_of_life::board::DEFAULT_BOARD_SIZE;
+use game_of_life::heatmap::HeatmapMeasure;
+use game_of_life::render::{parse_colour, Snapshot};
+use game_of_life::rule::Rule;
+use game_of_life::simulation::{BoundingBox, Engine};
+use game_of_life::soup::{Soup, Symmetry};
+use game_of_life::theme::Theme;
+use game_of_life::topology::Topology;
+
+const DEFAULT_SOUP_SIZE: usize = 16;
+// The viewer draws the board into a single texture, which graphics cards support up to this size
+const MAX_SIZE: usize = 1 << 14;
+
+pub struct Options {
+    pub rule: Option<Rule>,
+    pub width: usize,
+    pub height: usize,
+    pub topology: Topology,
+    pub pattern: Option<PathBuf>,
+    pub threads: usize,
+    pub headless: bool,
+    pub engine: Engine,
+    pub generations: u64,
+    pub every: Option<u64>,
+    pub undo_memory: usize,
+    pub history_memory: usize,
+    // Generations jumped over with page up and page down
+    pub seek_step: u64,
+    pub auto_pause: AutoPause,
+    pub seed: Option<u64>,
+    pub soup: Option<(usize, usize)>,
+    pub density: f64,
+    pub symmetry: Symmetry,
+    pub search: Option<u64>,
+    pub max_generations: u64,
+    pub output: Option<PathBuf>,
+    pub snapshot: Snapshot,
+    pub png: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub record_from: Option<u64>,
+    pub record_to: Option<u64>,
+    pub record_every: u64,
+    pub frame_delay: u32,
+    pub age_colours: AgeColours,
+    pub heatmap: Option<PathBuf>,
+    pub heatmap_measure: HeatmapMeasure,
+    pub heatmap_window: usize,
+    pub themes: Vec<Theme>,
+    // Index of the theme to start with
+    pub theme: usize,
+    // Cells between the major grid lines of the viewer, None to leave them out
+    pub major_grid: Option<u32>,
+    pub axes: bool,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Options, String> {
+        Options::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl Iterator<Item=String>) -> Result<Options, String> {
+        let mut options = Options {
+            rule: None,
+            width: DEFAULT_BOARD_SIZE,
+            height: DEFAULT_BOARD_SIZE,
+            topology: Topology::Torus,
+            pattern: None,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            headless: false,
+            engine: Engine::Grid,
+            generations: 100,
+            every: None,
+            undo_memory: 64 * 1024 * 1024,
+            history_memory: 256 * 1024 * 1024,
+            seek_step: 100,
+            auto_pause: AutoPause::default(),
+            seed: None,
+            soup: None,
+            density: 0.5,
+            symmetry: Symmetry::C1,
+            search: None,
+            max_generations: 1 << 16,
+            output: None,
+            snapshot: Snapshot::default(),
+            png: None,
+            record: None,
+            record_from: None,
+            record_to: None,
+            record_every: 1,
+            frame_delay: 100,
+            age_colours: AgeColours::default(),
+            heatmap: None,
+            heatmap_measure: HeatmapMeasure::Changes,
+            heatmap_window: 100,
+            themes: Vec::new(),
+            theme: 0,
+            major_grid: Some(10),
+            axes: false,
+        };
+        let mut themes_path = None;
+        let mut theme_name = None;
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rule" => {
+                    options.rule = Some(Rule::parse(&next_value(&mut args, &arg)?)?);
+                }
+                "--size" => {
+                    let (width, height) = parse_size(&next_value(&mut args, &arg)?)?;
+                    options.width = width;
+                    options.height = height;
+                }
+                "--topology" => {
+                    options.topology = Topology::parse(&next_value(&mut args, &arg)?)?;
+                }
+                "--pattern" => {
+                    options.pattern = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--threads" => {
+                    options.threads = parse_count(&next_value(&mut args, &arg)?, &arg)? as usize;
+                }
+                "--undo-memory" => {
+                    let megabytes = parse_count(&next_value(&mut args, &arg)?, &arg)?;
+                    options.undo_memory = megabytes as usize * 1024 * 1024;
+                }
+                "--history-memory" => {
+                    let megabytes = parse_count(&next_value(&mut args, &arg)?, &arg)?;
+                    options.history_memory = megabytes as usize * 1024 * 1024;
+                }
+                "--seek-step" => {
+                    options.seek_step = parse_count(&next_value(&mut args, &arg)?, &arg)?;
+                }
+                "--pause-on-extinction" => {
+                    options.auto_pause.on_extinction = true;
+                }
+                "--pause-on-cycle" => {
+                    let period = parse_count(&next_value(&mut args, &arg)?, &arg)?;
+                    options.auto_pause.max_period = Some(period as i128);
+                }
+                "--pause-at" => {
+                    let generation = parse_generations(&next_value(&mut args, &arg)?)?;
+                    options.auto_pause.at_generation = Some(generation as i128);
+                }
+                "--seed" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.seed = Some(value.parse()
+                        .map_err(|_| format!("Invalid value '{}' for argument '{}'", value, arg))?);
+                }
+                "--soup" => {
+                    options.soup = Some(parse_size(&next_value(&mut args, &arg)?)?);
+                }
+                "--density" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.density = value.parse()
+                        .map_err(|_| format!("Invalid value '{}' for argument '{}'", value, arg))?;
+                }
+                "--symmetry" => {
+                    options.symmetry = Symmetry::parse(&next_value(&mut args, &arg)?)?;
+                }
+                "--headless" => {
+                    options.headless = true;
+                }
+                "--engine" => {
+                    options.engine = Engine::parse(&next_value(&mut args, &arg)?)?;
+                }
+                "--generations" => {
+                    options.generations = parse_generations(&next_value(&mut args, &arg)?)?;
+                }
+                "--every" => {
+                    options.every = Some(parse_count(&next_value(&mut args, &arg)?, &arg)?);
+                }
+                "--search" => {
+                    options.search = Some(parse_count(&next_value(&mut args, &arg)?, &arg)?);
+                }
+                "--max-generations" => {
+                    options.max_generations = parse_generations(&next_value(&mut args, &arg)?)?;
+                }
+                "--output" => {
+                    options.output = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--cell-size" => {
+                    options.snapshot.cell_size = parse_count(&next_value(&mut args, &arg)?, &arg)? as u32;
+                }
+                "--no-grid" => {
+                    options.snapshot.grid = false;
+                }
+                "--region" => {
+                    options.snapshot.region = Some(parse_region(&next_value(&mut args, &arg)?)?);
+                }
+                "--png" => {
+                    options.png = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--record" => {
+                    options.record = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--record-from" => {
+                    options.record_from = Some(parse_generations(&next_value(&mut args, &arg)?)?);
+                }
+                "--record-to" => {
+                    options.record_to = Some(parse_generations(&next_value(&mut args, &arg)?)?);
+                }
+                "--record-every" => {
+                    options.record_every = parse_count(&next_value(&mut args, &arg)?, &arg)?;
+                }
+                "--frame-delay" => {
+                    options.frame_delay = parse_count(&next_value(&mut args, &arg)?, &arg)? as u32;
+                }
+                "--colouring" => {
+                    options.age_colours.colouring = Colouring::parse(&next_value(&mut args, &arg)?)?;
+                }
+                "--max-age" => {
+                    options.age_colours.max_age = parse_count(&next_value(&mut args, &arg)?, &arg)? as u32;
+                }
+                "--trail-length" => {
+                    options.age_colours.trail_length = parse_count(&next_value(&mut args, &arg)?, &arg)? as u32;
+                }
+                "--newborn-colour" => {
+                    options.age_colours.newborn = parse_colour(&next_value(&mut args, &arg)?)?;
+                }
+                "--old-colour" => {
+                    options.age_colours.old = parse_colour(&next_value(&mut args, &arg)?)?;
+                }
+                "--trail-colour" => {
+                    options.age_colours.trail = parse_colour(&next_value(&mut args, &arg)?)?;
+                }
+                "--heatmap" => {
+                    options.heatmap = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--heatmap-measure" => {
+                    options.heatmap_measure = HeatmapMeasure::parse(&next_value(&mut args, &arg)?)?;
+                }
+                "--heatmap-window" => {
+                    options.heatmap_window = parse_count(&next_value(&mut args, &arg)?, &arg)? as usize;
+                }
+                "--major-grid" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.major_grid = match value.parse::<u32>() {
+                        Ok(0) => None,
+                        Ok(cells) => Some(cells),
+                        Err(_) => return Err(format!("Invalid value '{}' for argument '{}'", value, arg)),
+                    };
+                }
+                "--axes" => {
+                    options.axes = true;
+                }
+                "--themes" => {
+                    themes_path = Some(PathBuf::from(next_value(&mut args, &arg)?));
+                }
+                "--theme" => {
+                    theme_name = Some(next_value(&mut args, &arg)?);
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+
+        // Reports a soup size that doesn't fit the symmetry or an invalid density right away
+        options.soup(0).grid()?;
+
+        options.themes = match themes_path {
+            Some(path) => Theme::load(&path)?,
+            None => Theme::built_in(),
+        };
+        if let Some(name) = theme_name {
+            options.theme = Theme::find(&options.themes, &name)?;
+        }
+        options.snapshot.palette = options.themes[options.theme].palette;
+        Ok(options)
+    }
+
+    /// The soup of the size, density and symmetry given on the command line
+    pub fn soup(&self, seed: u64) -> Soup {
+        let (width, height) = self.soup.unwrap_or((DEFAULT_SOUP_SIZE, DEFAULT_SOUP_SIZE));
+        Soup {
+            density: self.density,
+            symmetry: self.symmetry,
+            ..Soup::new(seed, width, height)
+        }
+    }
+}
+
+fn next_value(args: &mut impl Iterator<Item=String>, arg: &str) -> Result<String, String> {
+    args.next().ok_or(format!("Missing value for argument '{}'", arg))
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid board size '{}', expected the form 1920x1080", value);
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: usize = width.trim().parse().map_err(|_| invalid())?;
+    let height: usize = height.trim().parse().map_err(|_| invalid())?;
+
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(format!("Size '{}' is too large, the limit is {} cells on each side", value, MAX_SIZE));
+    }
+    Ok((width, height))
+}
+
+// A rectangle of cells written as x,y,widthxheight, like 10,20,64x48
+fn parse_region(value: &str) -> Result<BoundingBox, String> {
+    let invalid = || format!("Invalid region '{}', expected the form 10,20,64x48", value);
+    let mut parts = value.splitn(3, ',');
+    let x: i64 = parts.next().and_then(|x| x.trim().parse().ok()).ok_or_else(invalid)?;
+    let y: i64 = parts.next().and_then(|y| y.trim().parse().ok()).ok_or_else(invalid)?;
+    let (width, height) = parse_size(parts.next().ok_or_else(invalid)?).map_err(|_| invalid())?;
+
+    Ok(BoundingBox { x, y, width: width as u64, height: height as u64 })
+}
+
+fn parse_count(value: &str, arg: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid value '{}' for argument '{}'", value, arg)),
+    }
+}
+
+// Accepts plain numbers as well as powers of two written as 2^40
+fn parse_generations(value: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid number of generations '{}'", value);
+
+    match value.split_once('^') {
+        Some(("2", power)) => match power.parse::<u32>() {
+            Ok(power) if power < 64 => Ok(1 << power),
+            _ => Err(invalid()),
+        },
+        Some(_) => Err(invalid()),
+        None => value.parse().map_err(|_| invalid()),
+    }
+}
@@ -0,0 +1,216 @@
+use std::borrow::Cow;
+
+use crate::board::Board;
+use crate::rule::Rule;
+
+// A row above or below the row being computed, as seen through the topology of the board, with
+// the cells just beyond its left and right edge
+struct NeighbourRow<'a> {
+    words: Cow<'a, [u64]>,
+    left: u64,
+    right: u64,
+}
+
+/// Computes the next generation of the rows starting at `first_row`, writing the packed words of
+/// as many rows as fit in `output`. Neighbours are counted 64 cells at a time with bitwise adders.
+pub fn step_rows(board: &Board, rule: &Rule, first_row: usize, output: &mut [u64]) {
+    let words_per_row = board.words_per_row();
+    if words_per_row == 0 {
+        return;
+    }
+
+    let last_mask = match board.width() % 64 {
+        0 => u64::MAX,
+        bits => (1u64 << bits) - 1,
+    };
+    let (birth, survival) = count_masks(rule);
+
+    for (offset, next) in output.chunks_mut(words_per_row).enumerate() {
+        let y = (first_row + offset) as i64;
+        let above = neighbour_row(board, y - 1);
+        let current = neighbour_row(board, y);
+        let below = neighbour_row(board, y + 1);
+
+        for (index, word) in next.iter_mut().enumerate() {
+            let neighbours = [
+                west(&above, index), above.words[index], east(&above, index, board.width()),
+                west(&current, index), east(&current, index, board.width()),
+                west(&below, index), below.words[index], east(&below, index, board.width()),
+            ];
+            let count = count_neighbours(neighbours);
+            let alive = current.words[index];
+
+            *word = (alive & matches(count, &survival)) | (!alive & matches(count, &birth));
+        }
+        next[words_per_row - 1] &= last_mask;
+    }
+}
+
+fn neighbour_row(board: &Board, y: i64) -> NeighbourRow<'_> {
+    let width = board.width() as i64;
+    let cell = |x: i64| -> u64 {
+        match board.resolve(x, y) {
+            Some((x, y)) => board.is_cell_occupied(x, y) as u64,
+            None => 0,
+        }
+    };
+    let (left, right) = (cell(-1), cell(width));
+
+    // Rows beyond the top or bottom edge are either missing, or another row that may be mirrored
+    let words = match board.resolve(0, y) {
+        None => Cow::Owned(vec![0; board.words_per_row()]),
+        Some((0, row)) => Cow::Borrowed(board.row(row)),
+        Some((_, row)) => Cow::Owned(reversed_row(board, row)),
+    };
+
+    NeighbourRow { words, left, right }
+}
+
+fn reversed_row(board: &Board, y: usize) -> Vec<u64> {
+    let mut words = vec![0; board.words_per_row()];
+    for x in 0..board.width() {
+        if board.is_cell_occupied(x, y) {
+            let mirrored = board.width() - 1 - x;
+            words[mirrored / 64] |= 1 << (mirrored % 64);
+        }
+    }
+    words
+}
+
+// Each bit holds the cell to the west of it
+fn west(row: &NeighbourRow, index: usize) -> u64 {
+    let carry = if index == 0 { row.left } else { row.words[index - 1] >> 63 };
+    (row.words[index] << 1) | carry
+}
+
+// Each bit holds the cell to the east of it
+fn east(row: &NeighbourRow, index: usize, width: usize) -> u64 {
+    let carry = if index == row.words.len() - 1 {
+        row.right << ((width - 1) % 64)
+    } else {
+        row.words[index + 1] << 63
+    };
+    (row.words[index] >> 1) | carry
+}
+
+/// Adds eight one bit values per lane into a four bit count, returned from low to high bit
+pub fn count_neighbours(cells: [u64; 8]) -> [u64; 4] {
+    let (above_sum, above_carry) = full_adder(cells[0], cells[1], cells[2]);
+    let (below_sum, below_carry) = full_adder(cells[5], cells[6], cells[7]);
+    let (side_sum, side_carry) = (cells[3] ^ cells[4], cells[3] & cells[4]);
+
+    let (ones, ones_carry) = full_adder(above_sum, below_sum, side_sum);
+    let (twos_sum, twos_carry) = full_adder(above_carry, below_carry, side_carry);
+    let (twos, twos_sum_carry) = (twos_sum ^ ones_carry, twos_sum & ones_carry);
+
+    [ones, twos, twos_carry ^ twos_sum_carry, twos_carry & twos_sum_carry]
+}
+
+fn full_adder(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let partial = a ^ b;
+    (partial ^ c, (a & b) | (c & partial))
+}
+
+/// The neighbour counts on which cells are born and on which they survive
+pub fn count_masks(rule: &Rule) -> (Vec<i8>, Vec<i8>) {
+    let birth = (0..=8).filter(|&count| rule.is_born(count)).collect();
+    let survival = (0..=8).filter(|&count| rule.survives(count)).collect();
+    (birth, survival)
+}
+
+/// Sets the bits of all lanes where the count equals one of the given counts
+pub fn matches(count: [u64; 4], counts: &[i8]) -> u64 {
+    counts.iter().fold(0, |result, &expected| {
+        let lanes = count.iter().enumerate().fold(u64::MAX, |lanes, (bit, &word)| {
+            if expected & (1 << bit) != 0 { lanes & word } else { lanes & !word }
+        });
+        result | lanes
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use strum::EnumCount;
+
+    use super::*;
+    use crate::simulation::perform_generation;
+    use crate::topology::Topology;
+
+    fn random_board(width: usize, height: usize, topology: Topology, seed: u64) -> Board {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = Board::with_size(width, height);
+        board.topology = topology;
+        for y in 0..height {
+            for x in 0..width {
+                board.set_cell(x, y, rng.gen_bool(0.4));
+            }
+        }
+        board
+    }
+
+    // The live cells of the next generation, counting the neighbours of each cell one by one
+    fn naive_generation(board: &Board, rule: &Rule) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let neighbours = (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .filter(|&offset| offset != (0, 0))
+                    .filter_map(|(dx, dy)| board.resolve(x as i64 + dx, y as i64 + dy))
+                    .filter(|&(x, y)| board.is_cell_occupied(x, y))
+                    .count() as i8;
+                if rule.next_state(board.is_cell_occupied(x, y), neighbours) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn counts_neighbours_of_every_combination() {
+        for combination in 0u32..256 {
+            let cells: [u64; 8] = std::array::from_fn(|bit| ((combination >> bit) & 1) as u64);
+            let count = count_neighbours(cells);
+            let total = count.iter().enumerate().map(|(bit, &word)| (word as u32 & 1) << bit).sum::<u32>();
+            assert_eq!(total, combination.count_ones());
+        }
+    }
+
+    #[test]
+    fn matches_naive_count_on_every_topology() {
+        let rules = ["B3/S23", "B36/S23", "B2/S", "B0123478/S01234678"].map(|rule| Rule::parse(rule).unwrap());
+        for width in [1, 2, 63, 64, 65, 130] {
+            for height in [1, 2, 5, 64] {
+                for topology in (0..Topology::COUNT).filter_map(Topology::from_repr) {
+                    for rule in &rules {
+                        let mut board = random_board(width, height, topology, (width * 1000 + height) as u64);
+                        for _ in 0..3 {
+                            let next = perform_generation(&board, rule, 1);
+                            assert_eq!(next.live_cells().collect::<Vec<_>>(), naive_generation(&board, rule),
+                                       "{}x{} {} {}", width, height, topology.name(), rule);
+                            board = next;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threads_match_a_single_thread() {
+        let rule = Rule::conway();
+        for topology in (0..Topology::COUNT).filter_map(Topology::from_repr) {
+            let mut board = random_board(1000, 1000, topology, 7);
+            for _ in 0..4 {
+                let next = perform_generation(&board, &rule, 1);
+                for threads in [2, 3, 4] {
+                    let threaded = perform_generation(&board, &rule, threads);
+                    assert!(threaded.live_cells().eq(next.live_cells()), "{} threads {}", threads, topology.name());
+                }
+                board = next;
+            }
+        }
+    }
+}
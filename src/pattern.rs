@@ -1,7 +1,12 @@
-use rand::{Rng, thread_rng};
+use std::fs;
+use std::path::Path;
+
+use rand::Rng;
 use strum::{EnumCount, FromRepr};
 
 use crate::direction::Direction;
+use crate::rule::Rule;
+use crate::{life, plaintext, rle};
 
 #[derive(FromRepr, Debug, PartialEq, EnumCount)]
 pub enum Pattern {
@@ -13,8 +18,7 @@ pub enum Pattern {
 }
 
 impl Pattern {
-    pub fn get_random_pattern() -> Pattern {
-        let mut rng = thread_rng();
+    pub fn get_random_pattern(rng: &mut impl Rng) -> Pattern {
         return Pattern::from_repr(rng.gen_range(0..Pattern::COUNT))
             .unwrap_or(Pattern::Glider);
     }
@@ -92,3 +96,72 @@ impl Pattern {
         rotated
     }
 }
+
+// Patterns are read into a grid of cells, which limits their width and height
+pub const MAX_PATTERN_SIZE: usize = 1 << 14;
+
+/// A pattern read from or written to a pattern file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PatternFile {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<Rule>,
+    pub grid: Vec<Vec<u8>>,
+}
+
+impl PatternFile {
+    pub fn load(path: &Path) -> Result<PatternFile, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read '{}': {}", path.display(), error))?;
+
+        match extension(path).as_str() {
+            "rle" => rle::parse(&contents),
+            "cells" => plaintext::parse(&contents),
+            "lif" | "life" => life::parse(&contents),
+            _ => Err(format!("Unsupported pattern file '{}'", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = match extension(path).as_str() {
+            "rle" => rle::write(self),
+            "cells" => plaintext::write(self),
+            "lif" | "life" => life::write_105(self),
+            _ => return Err(format!("Unsupported pattern file '{}'", path.display())),
+        };
+
+        fs::write(path, contents)
+            .map_err(|error| format!("Could not write '{}': {}", path.display(), error))
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Builds the smallest grid that contains all given cell coordinates
+pub fn grid_from_cells(cells: &[(i64, i64)]) -> Result<Vec<Vec<u8>>, String> {
+    if cells.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let min_x = cells.iter().map(|&(x, _)| x).min().unwrap();
+    let max_x = cells.iter().map(|&(x, _)| x).max().unwrap();
+    let min_y = cells.iter().map(|&(_, y)| y).min().unwrap();
+    let max_y = cells.iter().map(|&(_, y)| y).max().unwrap();
+
+    let side = |min: i64, max: i64| max.checked_sub(min)
+        .filter(|&distance| distance < MAX_PATTERN_SIZE as i64)
+        .map(|distance| distance as usize + 1);
+    let (width, height) = side(min_x, max_x).zip(side(min_y, max_y))
+        .ok_or_else(|| format!("Pattern is larger than the limit of {} cells on each side", MAX_PATTERN_SIZE))?;
+
+    let mut grid = vec![vec![0u8; width]; height];
+    for &(x, y) in cells {
+        grid[(y - min_y) as usize][(x - min_x) as usize] = 1;
+    }
+    Ok(grid)
+}
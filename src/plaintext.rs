@@ -0,0 +1,84 @@
+use crate::pattern::PatternFile;
+
+/// Parses a pattern in the plaintext `.cells` format, rows of `.` for dead and `O` for alive cells
+pub fn parse(input: &str) -> Result<PatternFile, String> {
+    let mut pattern = PatternFile::default();
+    let mut grid: Vec<Vec<u8>> = Vec::new();
+
+    for line in input.lines() {
+        let line = line.trim_end();
+
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(name) => pattern.name = Some(name.trim().to_string()),
+                None => pattern.comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+
+        let row = line.chars()
+            .map(|cell| match cell {
+                '.' => Ok(0),
+                'O' | '*' => Ok(1),
+                _ => Err(format!("Unexpected character '{}' in plaintext pattern", cell)),
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        grid.push(row);
+    }
+
+    // Rows may leave out their trailing dead cells
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in grid.iter_mut() {
+        row.resize(width, 0);
+    }
+
+    pattern.grid = grid;
+    Ok(pattern)
+}
+
+/// Writes a pattern in the plaintext `.cells` format
+pub fn write(pattern: &PatternFile) -> String {
+    let mut output = String::new();
+
+    if let Some(name) = &pattern.name {
+        output.push_str(&format!("!Name: {}\n", name));
+    }
+    for comment in &pattern.comments {
+        output.push_str(&format!("!{}\n", comment));
+    }
+
+    for row in &pattern.grid {
+        let line: String = row.iter().map(|&cell| if cell == 1 { 'O' } else { '.' }).collect();
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_patterns() {
+        let pattern = PatternFile {
+            name: Some("Beacon".to_string()),
+            comments: vec!["Period 2 oscillator".to_string()],
+            rule: None,
+            grid: vec![vec![1, 1, 0, 0], vec![1, 0, 0, 0], vec![0, 0, 0, 1], vec![0, 0, 1, 1], vec![0, 0, 0, 0]],
+        };
+        assert_eq!(parse(&write(&pattern)).unwrap(), pattern);
+    }
+
+    #[test]
+    fn pads_rows_without_trailing_dead_cells() {
+        let pattern = parse("!Name: Glider\n.O\n..O\nOOO\n").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.grid, vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]]);
+    }
+
+    #[test]
+    fn rejects_unknown_cells() {
+        assert!(parse(".O\nOX\n").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, RgbImage};
+
+use crate::board::Board;
+use crate::render::{fit_cell_size, Snapshot};
+use crate::simulation::{BoundingBox, Simulation};
+
+/// Records generations of a board or simulation and saves them as an animated GIF or PNG
+pub struct Recorder {
+    pub snapshot: Snapshot,
+    // Only every this many generations after the first recorded one is kept
+    pub every: u64,
+    // Time each frame is shown in milliseconds
+    pub frame_delay: u32,
+    frames: Vec<Vec<(i64, i64)>>,
+    // Area of the first recorded board, simulations are drawn around the cells of all frames
+    board_region: Option<BoundingBox>,
+    first_generation: Option<i128>,
+    last_generation: Option<i128>,
+}
+
+impl Recorder {
+    pub fn new(snapshot: Snapshot, every: u64, frame_delay: u32) -> Self {
+        Recorder {
+            snapshot,
+            every: every.max(1),
+            frame_delay,
+            frames: Vec::new(),
+            board_region: None,
+            first_generation: None,
+            last_generation: None,
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn first_generation(&self) -> Option<i128> {
+        self.first_generation
+    }
+
+    /// Adds the board as a frame, unless its generation was recorded already or falls between
+    /// the generations that are kept
+    pub fn capture_board(&mut self, board: &Board) {
+        if self.should_capture(board.generation()) {
+            self.board_region.get_or_insert(self.snapshot.board_region(board));
+            self.frames.push(board.live_cells().map(|(x, y)| (x as i64, y as i64)).collect());
+        }
+    }
+
+    pub fn capture_simulation(&mut self, simulation: &dyn Simulation) {
+        if self.should_capture(simulation.generation()) {
+            self.frames.push(simulation.live_cells());
+        }
+    }
+
+    fn should_capture(&mut self, generation: i128) -> bool {
+        let first = *self.first_generation.get_or_insert(generation);
+        let recorded = self.last_generation.is_some_and(|last| generation <= last);
+        if recorded || (generation - first) % self.every as i128 != 0 {
+            return false;
+        }
+        self.last_generation = Some(generation);
+        true
+    }
+
+    /// Saves the frames as an APNG for the .png and .apng extensions and as a GIF otherwise
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if self.frames.is_empty() {
+            return Err("Nothing was recorded".to_string());
+        }
+
+        let region = self.snapshot.region.or(self.board_region).unwrap_or_else(|| self.cells_region());
+        // Fails before the file is created when even the smallest frames are too large
+        fit_cell_size(self.snapshot.cell_size, region.width, region.height)?;
+        let frames = self.frames.iter().map(|cells| self.snapshot.render_cells(cells, region));
+        let write_error = |error: String| format!("Could not write '{}': {}", path.display(), error);
+
+        let file = BufWriter::new(File::create(path).map_err(|error| write_error(error.to_string()))?);
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        match extension.to_lowercase().as_str() {
+            "png" | "apng" => self.write_apng(file, frames),
+            _ => self.write_gif(file, frames),
+        }.map_err(write_error)
+    }
+
+    // The smallest rectangle around the live cells of every frame
+    fn cells_region(&self) -> BoundingBox {
+        let mut cells = self.frames.iter().flatten();
+        let &(x, y) = match cells.next() {
+            Some(cell) => cell,
+            None => return BoundingBox { x: 0, y: 0, width: 1, height: 1 },
+        };
+        let (left, top, right, bottom) = cells.fold((x, y, x, y), |(left, top, right, bottom), &(x, y)| {
+            (left.min(x), top.min(y), right.max(x), bottom.max(y))
+        });
+
+        BoundingBox {
+            x: left,
+            y: top,
+            width: (right - left + 1) as u64,
+            height: (bottom - top + 1) as u64,
+        }
+    }
+
+    fn write_gif(&self, file: BufWriter<File>, frames: impl Iterator<Item=Result<RgbImage, String>>)
+                 -> Result<(), String> {
+        let mut encoder = GifEncoder::new(file);
+        encoder.set_repeat(Repeat::Infinite).map_err(|error| error.to_string())?;
+
+        for frame in frames {
+            let delay = Delay::from_numer_denom_ms(self.frame_delay, 1);
+            let frame = Frame::from_parts(DynamicImage::ImageRgb8(frame?).to_rgba8(), 0, 0, delay);
+            encoder.encode_frame(frame).map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn write_apng(&self, file: BufWriter<File>, mut frames: impl Iterator<Item=Result<RgbImage, String>>)
+                  -> Result<(), String> {
+        let first = frames.next().unwrap()?;
+        let mut encoder = png::Encoder::new(file, first.width(), first.height());
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, 0).map_err(|error| error.to_string())?;
+        encoder.set_frame_delay(self.frame_delay.min(u16::MAX as u32) as u16, 1000)
+            .map_err(|error| error.to_string())?;
+
+        let mut writer = encoder.write_header().map_err(|error| error.to_string())?;
+        for frame in std::iter::once(Ok(first)).chain(frames) {
+            writer.write_image_data(frame?.as_raw()).map_err(|error| error.to_string())?;
+        }
+        writer.finish().map_err(|error| error.to_string())
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use image::{Rgb, RgbImage};
+
+use crate::board::Board;
+use crate::simulation::{BoundingBox, Simulation};
+
+// The colours of the dark theme of the viewer, the grid is the background brightened by 30%
+pub const FOREGROUND: Rgb<u8> = Rgb([19, 76, 207]);
+pub const BACKGROUND: Rgb<u8> = Rgb([29, 29, 29]);
+pub const GRID: Rgb<u8> = Rgb([38, 38, 38]);
+// Images are kept below this many pixels, about 200 MB, by drawing the cells smaller
+pub const MAX_IMAGE_PIXELS: u64 = 1 << 26;
+
+/// The colours a board is drawn in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub foreground: Rgb<u8>,
+    pub background: Rgb<u8>,
+    pub grid: Rgb<u8>,
+    // Around the board in the viewer when it doesn't fill the window
+    pub outside: Rgb<u8>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            foreground: FOREGROUND,
+            background: BACKGROUND,
+            grid: GRID,
+            outside: Rgb([0, 0, 0]),
+        }
+    }
+}
+
+/// How a board is drawn to an image, in the colours of a theme of the viewer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    // Width and height of a cell in pixels
+    pub cell_size: u32,
+    // Grid lines are drawn over the first pixel row and column of each cell, like in the viewer
+    pub grid: bool,
+    // The cells to draw, defaults to the whole board or the live cells of a simulation
+    pub region: Option<BoundingBox>,
+    pub palette: Palette,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            cell_size: 8,
+            grid: true,
+            region: None,
+            palette: Palette::default(),
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn render_board(&self, board: &Board) -> Result<RgbImage, String> {
+        let cells: Vec<(i64, i64)> = board.live_cells().map(|(x, y)| (x as i64, y as i64)).collect();
+        self.render_cells(&cells, self.board_region(board))
+    }
+
+    pub fn render_simulation(&self, simulation: &dyn Simulation) -> Result<RgbImage, String> {
+        let region = self.region.or(simulation.bounding_box()).unwrap_or(BoundingBox {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        });
+        self.render_cells(&simulation.live_cells(), region)
+    }
+
+    /// The region drawn of a board, the whole board unless a region was given
+    pub fn board_region(&self, board: &Board) -> BoundingBox {
+        self.region.unwrap_or(BoundingBox {
+            x: 0,
+            y: 0,
+            width: board.width() as u64,
+            height: board.height() as u64,
+        })
+    }
+
+    /// Draws the cells that lie within the region, with smaller cells than the cell size when the
+    /// image would be larger than `MAX_IMAGE_PIXELS`
+    pub fn render_cells(&self, cells: &[(i64, i64)], region: BoundingBox) -> Result<RgbImage, String> {
+        let cell_size = fit_cell_size(self.cell_size, region.width, region.height)?;
+        let mut image = RgbImage::from_pixel(region.width as u32 * cell_size,
+                                             region.height as u32 * cell_size, self.palette.background);
+
+        for &(x, y) in cells {
+            let (x, y) = (x - region.x, y - region.y);
+            if x < 0 || y < 0 || x >= region.width as i64 || y >= region.height as i64 {
+                continue;
+            }
+            let (x, y) = (x as u32, y as u32);
+            for pixel_y in y * cell_size..(y + 1) * cell_size {
+                for pixel_x in x * cell_size..(x + 1) * cell_size {
+                    image.put_pixel(pixel_x, pixel_y, self.palette.foreground);
+                }
+            }
+        }
+
+        if self.grid && cell_size > 1 {
+            for (pixel_x, pixel_y, pixel) in image.enumerate_pixels_mut() {
+                if pixel_x % cell_size == 0 || pixel_y % cell_size == 0 {
+                    *pixel = self.palette.grid;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// The largest cell size up to the given one that keeps an image of the cells within
+/// `MAX_IMAGE_PIXELS`, fails when even single pixel cells don't fit
+pub fn fit_cell_size(cell_size: u32, width: u64, height: u64) -> Result<u32, String> {
+    let cells = width.checked_mul(height)
+        .filter(|&cells| cells <= MAX_IMAGE_PIXELS)
+        .ok_or(format!("An image of {}x{} cells is larger than the limit of {} pixels", width, height,
+                       MAX_IMAGE_PIXELS))?;
+    if cells == 0 {
+        return Ok(cell_size.max(1));
+    }
+
+    let largest = ((MAX_IMAGE_PIXELS / cells) as f64).sqrt() as u64;
+    Ok((cell_size as u64).clamp(1, largest.max(1)) as u32)
+}
+
+/// Parses a colour written in hexadecimal like #134ccf, the # is optional
+pub fn parse_colour(value: &str) -> Result<Rgb<u8>, String> {
+    let invalid = || format!("Invalid colour '{}', expected the form #134ccf", value);
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+pub fn save_png(image: &RgbImage, path: &Path) -> Result<(), String> {
+    image.save_with_format(path, image::ImageFormat::Png)
+        .map_err(|error| format!("Could not write '{}': {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_cells_of_large_regions_in_the_image_limit() {
+        assert_eq!(fit_cell_size(8, 128, 128), Ok(8));
+        assert_eq!(fit_cell_size(8, 4096, 4096), Ok(2));
+        assert_eq!(fit_cell_size(8, 8192, 8192), Ok(1));
+        assert_eq!(fit_cell_size(0, 10, 10), Ok(1));
+        assert!(fit_cell_size(8, 8193, 8192).is_err());
+        assert!(fit_cell_size(8, u64::MAX, 2).is_err());
+    }
+}
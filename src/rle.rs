@@ -0,0 +1,303 @@
+use crate::pattern::{PatternFile, MAX_PATTERN_SIZE};
+use crate::rule::Rule;
+
+const MAX_LINE_LENGTH: usize = 70;
+
+/// Parses a pattern in the Run Length Encoded format used by Golly and the LifeWiki
+pub fn parse(input: &str) -> Result<PatternFile, String> {
+    let mut pattern = PatternFile::default();
+    let mut size: Option<(usize, usize)> = None;
+    let mut body = String::new();
+
+    for line in input.lines() {
+        let line = line.trim();
+
+        if size.is_none() {
+            if let Some(comment) = line.strip_prefix('#') {
+                parse_comment_line(comment, &mut pattern);
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let (width, height, rule) = parse_header(line)?;
+            size = Some((width, height));
+            pattern.rule = rule;
+            continue;
+        }
+
+        // Comment lines may follow the header as well
+        if let Some(comment) = line.strip_prefix('#') {
+            parse_comment_line(comment, &mut pattern);
+            continue;
+        }
+
+        body.push_str(line);
+        if line.contains('!') {
+            break;
+        }
+    }
+
+    let (width, height) = size.ok_or("Missing RLE header line".to_string())?;
+    pattern.grid = parse_body(&body, width, height)?;
+    Ok(pattern)
+}
+
+fn parse_comment_line(comment: &str, pattern: &mut PatternFile) {
+    let mut chars = comment.chars();
+    let tag = chars.next();
+    let text = chars.as_str().trim().to_string();
+
+    match tag {
+        Some('N') => pattern.name = Some(text),
+        Some('C') | Some('c') | Some('O') => pattern.comments.push(text),
+        _ => (),
+    }
+}
+
+fn parse_header(line: &str) -> Result<(usize, usize, Option<Rule>), String> {
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+
+    // Golly appends the bounded grid to the rule (B3/S23:T100,100), the part after the comma
+    // in the grid size has no key and is skipped along with the grid itself
+    for (key, value) in line.split(',').filter_map(|entry| entry.split_once('=')) {
+        let value = value.trim();
+
+        match key.trim() {
+            "x" => width = value.parse().ok(),
+            "y" => height = value.parse().ok(),
+            "rule" => rule = Some(Rule::parse(value.split(':').next().unwrap())?),
+            _ => (),
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) if width > MAX_PATTERN_SIZE || height > MAX_PATTERN_SIZE =>
+            Err(format!("Pattern of {}x{} cells is too large, the limit is {} cells on each side", width,
+                        height, MAX_PATTERN_SIZE)),
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(format!("Invalid RLE header '{}'", line)),
+    }
+}
+
+fn parse_body(body: &str, width: usize, height: usize) -> Result<Vec<Vec<u8>>, String> {
+    // Rows grow as their cells are set, instead of trusting the header size up front
+    let mut grid: Vec<Vec<u8>> = Vec::new();
+    let mut x = 0;
+    let mut y = 0;
+    let mut count = 0usize;
+
+    // Runs may go past the header size of a pattern, but not past the size limit
+    let too_large = || format!("Pattern is larger than the limit of {} cells on each side", MAX_PATTERN_SIZE);
+
+    for tag in body.chars() {
+        if let Some(digit) = tag.to_digit(10) {
+            count = count.checked_mul(10)
+                .and_then(|count| count.checked_add(digit as usize))
+                .filter(|&count| count <= MAX_PATTERN_SIZE)
+                .ok_or_else(too_large)?;
+            continue;
+        }
+
+        let run = count.max(1);
+        count = 0;
+
+        match tag {
+            '!' => break,
+            '$' => {
+                y += run;
+                x = 0;
+                if y > MAX_PATTERN_SIZE {
+                    return Err(too_large());
+                }
+            }
+            'b' | '.' => {
+                x += run;
+                if x > MAX_PATTERN_SIZE {
+                    return Err(too_large());
+                }
+            }
+            // Any other state is treated as alive, which covers multi-state patterns
+            'o' | 'A'..='X' => {
+                if y >= MAX_PATTERN_SIZE || x + run > MAX_PATTERN_SIZE {
+                    return Err(too_large());
+                }
+                for _ in 0..run {
+                    set_grid_cell(&mut grid, x, y);
+                    x += 1;
+                }
+            }
+            tag if tag.is_whitespace() => (),
+            _ => return Err(format!("Unexpected character '{}' in RLE body", tag)),
+        }
+    }
+
+    // The body leaves out dead cells at the end of rows and empty rows at the end, which the header
+    // size keeps. Patterns that don't match their header size still load, the grid grows to fit them.
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0).max(width);
+    grid.resize(grid.len().max(height), Vec::new());
+    for row in grid.iter_mut() {
+        row.resize(width, 0);
+    }
+    Ok(grid)
+}
+
+fn set_grid_cell(grid: &mut Vec<Vec<u8>>, x: usize, y: usize) {
+    if grid.len() <= y {
+        grid.resize(y + 1, Vec::new());
+    }
+    let row = &mut grid[y];
+    if row.len() <= x {
+        row.resize(x + 1, 0);
+    }
+    row[x] = 1;
+}
+
+/// Writes a pattern in the Run Length Encoded format
+pub fn write(pattern: &PatternFile) -> String {
+    let mut output = String::new();
+
+    if let Some(name) = &pattern.name {
+        output.push_str(&format!("#N {}\n", name));
+    }
+    for comment in &pattern.comments {
+        output.push_str(&format!("#C {}\n", comment));
+    }
+
+    let height = pattern.grid.len();
+    let width = pattern.grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    let rule = pattern.rule.unwrap_or_default();
+    output.push_str(&format!("x = {}, y = {}, rule = {}\n", width, height, rule));
+
+    let mut line = String::new();
+    let mut empty_rows = 0;
+    let mut first_row = true;
+
+    for row in &pattern.grid {
+        let runs = row_runs(row);
+        if runs.is_empty() {
+            empty_rows += 1;
+            continue;
+        }
+
+        // Every row after the first ends the previous one, empty rows in between are merged
+        let line_ends = if first_row { empty_rows } else { empty_rows + 1 };
+        if line_ends > 0 {
+            push_run(&mut output, &mut line, line_ends, '$');
+        }
+        empty_rows = 0;
+        first_row = false;
+
+        for (length, tag) in runs {
+            push_run(&mut output, &mut line, length, tag);
+        }
+    }
+
+    push_run(&mut output, &mut line, 1, '!');
+    output.push_str(&line);
+    output.push('\n');
+    output
+}
+
+// Runs of alive and dead cells in a row, without the dead cells at the end of the row
+pub(crate) fn row_runs(row: &[u8]) -> Vec<(usize, char)> {
+    let mut runs: Vec<(usize, char)> = Vec::new();
+
+    for &cell in row {
+        let tag = if cell == 1 { 'o' } else { 'b' };
+        match runs.last_mut() {
+            Some((length, last)) if *last == tag => *length += 1,
+            _ => runs.push((1, tag)),
+        }
+    }
+
+    if let Some((_, 'b')) = runs.last() {
+        runs.pop();
+    }
+    runs
+}
+
+fn push_run(output: &mut String, line: &mut String, length: usize, tag: char) {
+    let run = if length > 1 {
+        format!("{}{}", length, tag)
+    } else {
+        tag.to_string()
+    };
+
+    if line.len() + run.len() > MAX_LINE_LENGTH {
+        output.push_str(line);
+        output.push('\n');
+        line.clear();
+    }
+    line.push_str(&run);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_grid(width: usize, height: usize, seed: u64) -> Vec<Vec<u8>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..height).map(|_| (0..width).map(|_| rng.gen_bool(0.3) as u8).collect()).collect()
+    }
+
+    #[test]
+    fn round_trips_patterns() {
+        for (width, height) in [(0, 0), (1, 1), (3, 3), (100, 7), (7, 100), (300, 40)] {
+            let pattern = PatternFile {
+                name: Some("Random".to_string()),
+                comments: vec!["First comment".to_string(), "Second comment".to_string()],
+                rule: Some(Rule::parse("B36/S23").unwrap()),
+                grid: random_grid(width, height, (width * height) as u64),
+            };
+            let written = write(&pattern);
+            assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH), "{}", written);
+            assert_eq!(parse(&written).unwrap(), pattern);
+        }
+    }
+
+    #[test]
+    fn keeps_empty_rows_and_columns() {
+        let mut grid = vec![vec![0; 5]; 6];
+        grid[1][1] = 1;
+        grid[4][3] = 1;
+        let pattern = PatternFile { grid, ..PatternFile::default() };
+        assert_eq!(parse(&write(&pattern)).unwrap().grid, pattern.grid);
+    }
+
+    #[test]
+    fn parses_comments_after_the_header() {
+        let pattern = parse("#N Glider\nx = 3, y = 3, rule = B3/S23\n#C Moves south east\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.name.as_deref(), Some("Glider"));
+        assert_eq!(pattern.comments, vec!["Moves south east"]);
+        assert_eq!(pattern.grid, vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]]);
+    }
+
+    #[test]
+    fn grows_patterns_larger_than_their_header() {
+        let pattern = parse("x = 1, y = 1\n3o$o!").unwrap();
+        assert_eq!(pattern.grid, vec![vec![1, 1, 1], vec![1, 0, 0]]);
+    }
+
+    #[test]
+    fn rejects_patterns_beyond_the_size_limit() {
+        assert!(parse("x = 100000, y = 1\no!").is_err());
+        assert!(parse("x = 1, y = 1\n99999999999999999999999o!").is_err());
+        assert!(parse("x = 1, y = 1\n16384bo!").is_err());
+        assert!(parse("x = 1, y = 1\n16384$o!").is_err());
+        assert!(parse("x = 1, y = 1\n16383bo2o!").is_err());
+    }
+
+    #[test]
+    fn accepts_runs_up_to_the_size_limit() {
+        assert_eq!(parse("x = 16384, y = 1\n16384b!").unwrap().grid, vec![vec![0; 16384]]);
+        assert_eq!(parse("x = 1, y = 1\n16383bo!").unwrap().grid[0].len(), 16384);
+        assert_eq!(parse("x = 1, y = 1\n16383$o!").unwrap().grid.len(), 16384);
+        assert_eq!(parse("x = 1, y = 1\no16384$!").unwrap().grid, vec![vec![1]]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::str::FromStr;
+
+use strum::{EnumCount, FromRepr};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        Rule::parse("B3/S23").unwrap()
+    }
+
+    /// Parses a Life-like rulestring, either in B/S notation (`B36/S23`) or in the older S/B
+    /// notation used by Life 1.05 files (`23/36`).
+    pub fn parse(rulestring: &str) -> Result<Rule, String> {
+        let rulestring = rulestring.trim();
+        let parts: Vec<&str> = rulestring.split('/').collect();
+        if parts.len() != 2 {
+            return Err(format!("Invalid rule '{}', expected the form B3/S23", rulestring));
+        }
+
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in &parts {
+            match part.chars().next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => birth = Some(parse_counts(&part[1..], rulestring)?),
+                Some('S') => survival = Some(parse_counts(&part[1..], rulestring)?),
+                _ => (),
+            }
+        }
+
+        // Without prefixes the rule is in S/B notation
+        if birth.is_none() && survival.is_none() {
+            survival = Some(parse_counts(parts[0], rulestring)?);
+            birth = Some(parse_counts(parts[1], rulestring)?);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => Err(format!("Invalid rule '{}', expected the form B3/S23", rulestring)),
+        }
+    }
+
+    pub fn is_born(&self, neighbours: i8) -> bool {
+        self.birth[neighbours as usize]
+    }
+
+    pub fn survives(&self, neighbours: i8) -> bool {
+        self.survival[neighbours as usize]
+    }
+
+    pub fn next_state(&self, active: bool, neighbours: i8) -> bool {
+        if active {
+            self.survives(neighbours)
+        } else {
+            self.is_born(neighbours)
+        }
+    }
+
+    /// Formats the rule in the S/B notation used by Life 1.05 files, e.g. `23/3`
+    pub fn to_survival_birth_string(&self) -> String {
+        format!("{}/{}", counts_to_string(&self.survival), counts_to_string(&self.birth))
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        (0..NamedRule::COUNT)
+            .filter_map(NamedRule::from_repr)
+            .find(|named| named.rule() == *self)
+            .map(|named| named.name())
+    }
+
+    /// Returns the next rule in the list of named rules, starting over at Conway's Life for
+    /// rules that are not in the list.
+    pub fn next_named(&self) -> Rule {
+        let index = (0..NamedRule::COUNT)
+            .find(|&index| NamedRule::from_repr(index).unwrap().rule() == *self)
+            .map(|index| (index + 1) % NamedRule::COUNT)
+            .unwrap_or(0);
+        NamedRule::from_repr(index).unwrap().rule()
+    }
+}
+
+fn counts_to_string(counts: &[bool; 9]) -> String {
+    (0..9).filter(|&count| counts[count]).map(|count| count.to_string()).collect()
+}
+
+fn parse_counts(digits: &str, rulestring: &str) -> Result<[bool; 9], String> {
+    let mut counts = [false; 9];
+    for digit in digits.chars() {
+        match digit.to_digit(10) {
+            Some(count) if count <= 8 => counts[count as usize] = true,
+            _ => return Err(format!("Invalid neighbour count '{}' in rule '{}'", digit, rulestring)),
+        }
+    }
+    Ok(counts)
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(rulestring: &str) -> Result<Self, Self::Err> {
+        Rule::parse(rulestring)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B{}/S{}", counts_to_string(&self.birth), counts_to_string(&self.survival))
+    }
+}
+
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum NamedRule {
+    Conway,
+    HighLife,
+    DayAndNight,
+    Seeds,
+    LifeWithoutDeath,
+    Maze,
+    Replicator,
+    TwoByTwo,
+}
+
+impl NamedRule {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NamedRule::Conway => "Life",
+            NamedRule::HighLife => "HighLife",
+            NamedRule::DayAndNight => "Day & Night",
+            NamedRule::Seeds => "Seeds",
+            NamedRule::LifeWithoutDeath => "Life without Death",
+            NamedRule::Maze => "Maze",
+            NamedRule::Replicator => "Replicator",
+            NamedRule::TwoByTwo => "2x2",
+        }
+    }
+
+    pub fn rulestring(&self) -> &'static str {
+        match *self {
+            NamedRule::Conway => "B3/S23",
+            NamedRule::HighLife => "B36/S23",
+            NamedRule::DayAndNight => "B3678/S34678",
+            NamedRule::Seeds => "B2/S",
+            NamedRule::LifeWithoutDeath => "B3/S012345678",
+            NamedRule::Maze => "B3/S12345",
+            NamedRule::Replicator => "B1357/S1357",
+            NamedRule::TwoByTwo => "B36/S125",
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        Rule::parse(self.rulestring()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(rule: &Rule) -> (Vec<i8>, Vec<i8>) {
+        ((0..9).filter(|&count| rule.is_born(count)).collect(), (0..9).filter(|&count| rule.survives(count)).collect())
+    }
+
+    #[test]
+    fn parses_both_notations() {
+        let highlife = Rule::parse("B36/S23").unwrap();
+        assert_eq!(counts(&highlife), (vec![3, 6], vec![2, 3]));
+        assert_eq!(Rule::parse("S23/B36").unwrap(), highlife);
+        assert_eq!(Rule::parse("23/36").unwrap(), highlife);
+        assert_eq!(Rule::parse("b36/s23").unwrap(), highlife);
+        assert_eq!(Rule::parse(" B36/S23 ").unwrap(), highlife);
+    }
+
+    #[test]
+    fn parses_empty_counts() {
+        assert_eq!(counts(&Rule::parse("B2/S").unwrap()), (vec![2], vec![]));
+        assert_eq!(counts(&Rule::parse("B/S012345678").unwrap()), (vec![], vec![0, 1, 2, 3, 4, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rulestring in ["B39/S23", "B3/S239", "B3x/S23", "B3/23", "23/S3", "B3/B23", "B3", "B3/S23/S1", ""] {
+            assert!(Rule::parse(rulestring).is_err(), "{}", rulestring);
+        }
+    }
+
+    #[test]
+    fn formats_both_notations() {
+        let rule = Rule::parse("S1357/B1357").unwrap();
+        assert_eq!(rule.to_string(), "B1357/S1357");
+        assert_eq!(Rule::parse("B2/S").unwrap().to_survival_birth_string(), "/2");
+        assert_eq!(Rule::parse(&Rule::parse("B3678/S34678").unwrap().to_survival_birth_string()).unwrap(),
+                   Rule::parse("B3678/S34678").unwrap());
+    }
+
+    #[test]
+    fn round_trips_named_rules() {
+        let mut rule = Rule::conway();
+        for index in 0..NamedRule::COUNT {
+            let named = NamedRule::from_repr(index).unwrap();
+            assert_eq!(rule, named.rule());
+            assert_eq!(rule.to_string(), named.rulestring());
+            assert_eq!(Rule::parse(&rule.to_string()).unwrap(), rule);
+            assert_eq!(rule.name(), Some(named.name()));
+            rule = rule.next_named();
+        }
+        assert_eq!(rule, Rule::conway());
+    }
+
+    #[test]
+    fn starts_over_at_conway_for_unnamed_rules() {
+        let rule = Rule::parse("B34/S34").unwrap();
+        assert_eq!(rule.name(), None);
+        assert_eq!(rule.next_named(), Rule::conway());
+    }
+}
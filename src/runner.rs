@@ -0,0 +1,26 @@
+use crate::options::Options;
+
+mod headless;
+mod options;
+mod search;
+
+// Runs headless mode or a soup search from the command line, without a window so that the output
+// stays on the console on every platform
+fn main() {
+    let options = exit_on_error(Options::from_args());
+
+    match options.search {
+        Some(soups) => exit_on_error(search::run(&options, soups)),
+        None => exit_on_error(headless::run(&options)),
+    }
+}
+
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
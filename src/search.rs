@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use game_of_life::census::{Census, KnownObjects};
+use game_of_life::pattern::PatternFile;
+use game_of_life::rule::Rule;
+use game_of_life::simulation::Simulation;
+use game_of_life::sparse::SparseBoard;
+
+use crate::options::Options;
+
+// Objects that turn up in nearly every soup and aren't worth reporting
+const COMMON_OBJECTS: [&str; 13] = [
+    "block", "blinker", "beehive", "glider", "loaf", "boat", "ship", "tub", "pond", "long boat",
+    "barge", "beacon", "toad",
+];
+
+// A soup has stabilised once its population repeats with a period up to this long
+const MAX_PERIOD: usize = 30;
+// The population has to repeat for this many generations, and at least four periods
+const STABLE_GENERATIONS: usize = 100;
+const CHECK_INTERVAL: u64 = 10;
+
+// Number of soups kept in each of the lists of the report
+const LEADERBOARD_SIZE: usize = 20;
+const EXAMPLE_SEEDS: usize = 5;
+const REPORT_INTERVAL: u64 = 1000;
+
+struct SoupResult {
+    seed: u64,
+    // Generation from which the population repeated, or None if it didn't stabilise in time
+    lifespan: Option<u64>,
+    population: i128,
+    rare_objects: Vec<String>,
+}
+
+#[derive(Default)]
+struct Report {
+    searched: u64,
+    // Seeds of the soups that didn't stabilise, only the first few are kept
+    unstable: (u64, Vec<u64>),
+    longest_lived: Vec<(u64, u64)>,
+    most_populated: Vec<(i128, u64)>,
+    // Seeds of the soups that produced each rare object, only the first few are kept
+    rare_objects: BTreeMap<String, (u64, Vec<u64>)>,
+    // Comments of the saved soups, a soup that shows up in several lists is saved with all of them
+    saved: HashMap<u64, Vec<String>>,
+}
+
+/// Runs seeded random soups on all threads until they stabilise, writing a report of the longest
+/// lived soups, the highest final populations and rare objects along with the RLE of those soups
+pub fn run(options: &Options, soups: u64) -> Result<(), String> {
+    let rule = options.rule.unwrap_or_default();
+    SparseBoard::new(rule)?;
+    options.soup(0).grid()?;
+
+    let first_seed = options.seed.unwrap_or_else(rand::random);
+    let output = options.output.clone()
+        .unwrap_or_else(|| PathBuf::from(format!("search-{}", first_seed)));
+    fs::create_dir_all(&output)
+        .map_err(|error| format!("Could not create '{}': {}", output.display(), error))?;
+    println!("Searching {} soups from seed {} with rule {}, writing to {}", soups, first_seed, rule,
+             output.display());
+
+    let known = KnownObjects::new(&rule);
+    let next_soup = AtomicU64::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut report = Report::default();
+
+    thread::scope(|scope| -> Result<(), String> {
+        for _ in 0..options.threads.max(1) {
+            let sender = sender.clone();
+            let next_soup = &next_soup;
+            let known = &known;
+            scope.spawn(move || loop {
+                let index = next_soup.fetch_add(1, Ordering::Relaxed);
+                if index >= soups {
+                    break;
+                }
+                let result = run_soup(options, rule, known, first_seed.wrapping_add(index));
+                if sender.send(result).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for result in receiver {
+            report.add(result, &output, options, rule)?;
+            if report.searched % REPORT_INTERVAL == 0 {
+                println!("Searched {} soups", report.searched);
+                report.write(&output, options, rule)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    report.write(&output, options, rule)?;
+    println!("Searched {} soups, report written to {}", report.searched, output.join("report.txt").display());
+    Ok(())
+}
+
+fn run_soup(options: &Options, rule: Rule, known: &KnownObjects, seed: u64) -> SoupResult {
+    let mut simulation = SparseBoard::new(rule).unwrap();
+    simulation.set_cells(&options.soup(seed).grid().unwrap(), 0, 0);
+
+    let mut populations = vec![simulation.population()];
+    let mut lifespan = None;
+    for generation in 1..=options.max_generations {
+        simulation.perform_generation();
+        populations.push(simulation.population());
+
+        if generation % CHECK_INTERVAL == 0 {
+            lifespan = stabilisation(&populations);
+            if lifespan.is_some() {
+                break;
+            }
+        }
+    }
+
+    let census = Census::of_cells(&simulation.live_cells(), known);
+    let rare_objects = census.entries.iter()
+        .filter(|entry| !entry.name.is_some_and(|name| COMMON_OBJECTS.contains(&name)))
+        .map(|entry| entry.name.map_or(format!("unnamed {}-cell object {}", entry.population, entry.code),
+                                       |name| name.to_string()))
+        .collect();
+
+    SoupResult {
+        seed,
+        lifespan,
+        population: simulation.population(),
+        rare_objects,
+    }
+}
+
+// Returns the generation from which the population repeats, if it has repeated long enough
+fn stabilisation(populations: &[i128]) -> Option<u64> {
+    let length = populations.len();
+
+    (1..=MAX_PERIOD).find_map(|period| {
+        let window = STABLE_GENERATIONS.max(period * 4);
+        if length < window + period {
+            return None;
+        }
+        if !(length - window..length).all(|i| populations[i] == populations[i - period]) {
+            return None;
+        }
+
+        let mut since = length - window;
+        while since > period && populations[since - 1] == populations[since - 1 - period] {
+            since -= 1;
+        }
+        Some((since - period) as u64)
+    })
+}
+
+impl Report {
+    // Soups that didn't stabilise or produced a rare object are saved right away, the soups of the
+    // leaderboards only when the report is written as they may still drop out
+    fn add(&mut self, result: SoupResult, output: &Path, options: &Options, rule: Rule) -> Result<(), String> {
+        self.searched += 1;
+
+        match result.lifespan {
+            Some(lifespan) => insert_leader(&mut self.longest_lived, (lifespan, result.seed)),
+            None => {
+                let (count, seeds) = &mut self.unstable;
+                *count += 1;
+                if seeds.len() < LEADERBOARD_SIZE {
+                    seeds.push(result.seed);
+                    self.save_soup(output, options, rule, result.seed, "Did not stabilise")?;
+                }
+            }
+        }
+        insert_leader(&mut self.most_populated, (result.population, result.seed));
+
+        for object in result.rare_objects {
+            let (count, seeds) = self.rare_objects.entry(object.clone()).or_default();
+            *count += 1;
+            if seeds.len() < EXAMPLE_SEEDS {
+                seeds.push(result.seed);
+                self.save_soup(output, options, rule, result.seed, &format!("Produces {}", object))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, output: &Path, options: &Options, rule: Rule) -> Result<(), String> {
+        let soup = options.soup(0);
+        let mut text = format!("Soups searched: {}\nRule: {}\nSoup: {}x{}, density {}, symmetry {}\n",
+                               self.searched, rule, soup.width, soup.height, soup.density,
+                               soup.symmetry.name());
+
+        let (unstable, seeds) = &self.unstable;
+        text.push_str(&format!("\nNot stabilised within {} generations: {} soups\n", options.max_generations,
+                               unstable));
+        for seed in seeds {
+            text.push_str(&format!("seed {}\n", seed));
+        }
+
+        text.push_str("\nLongest lived:\n");
+        for (lifespan, seed) in self.longest_lived.clone() {
+            text.push_str(&format!("seed {} stabilised at generation {}\n", seed, lifespan));
+            self.save_soup(output, options, rule, seed, &format!("Stabilised at generation {}", lifespan))?;
+        }
+
+        text.push_str("\nHighest final population:\n");
+        for (population, seed) in self.most_populated.clone() {
+            text.push_str(&format!("seed {} with population {}\n", seed, population));
+            self.save_soup(output, options, rule, seed, &format!("Final population {}", population))?;
+        }
+
+        text.push_str("\nRare objects:\n");
+        for (object, (count, seeds)) in &self.rare_objects {
+            let seeds: Vec<String> = seeds.iter().map(|seed| seed.to_string()).collect();
+            text.push_str(&format!("{} in {} soups, seeds {}\n", object, count, seeds.join(", ")));
+        }
+
+        let path = output.join("report.txt");
+        fs::write(&path, text).map_err(|error| format!("Could not write '{}': {}", path.display(), error))
+    }
+
+    // Writes the soup with the comments of every list it was saved for, unless it already has the comment
+    fn save_soup(&mut self, output: &Path, options: &Options, rule: Rule, seed: u64, comment: &str)
+                 -> Result<(), String> {
+        let comments = self.saved.entry(seed).or_default();
+        if comments.iter().any(|saved| saved == comment) {
+            return Ok(());
+        }
+        comments.push(comment.to_string());
+
+        let pattern = PatternFile {
+            name: Some(format!("Soup {}", seed)),
+            comments: comments.clone(),
+            rule: Some(rule),
+            grid: options.soup(seed).grid()?,
+        };
+        pattern.save(&output.join(format!("soup-{}.rle", seed)))
+    }
+}
+
+// Keeps the largest values sorted from the largest down, at most LEADERBOARD_SIZE of them
+fn insert_leader<T: Ord>(leaders: &mut Vec<(T, u64)>, entry: (T, u64)) {
+    let index = leaders.partition_point(|leader| leader.0 >= entry.0);
+    if index < LEADERBOARD_SIZE {
+        leaders.insert(index, entry);
+        leaders.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_still_population() {
+        let mut populations = vec![1, 2, 3];
+        populations.extend([5; 150]);
+        assert_eq!(stabilisation(&populations), Some(3));
+        assert_eq!(stabilisation(&[5; 150]), Some(0));
+    }
+
+    #[test]
+    fn finds_a_periodic_population() {
+        let mut populations: Vec<i128> = (100..120).collect();
+        populations.extend([5, 7, 9].iter().cycle().take(200));
+        assert_eq!(stabilisation(&populations), Some(20));
+    }
+
+    #[test]
+    fn waits_until_the_population_has_repeated_long_enough() {
+        assert_eq!(stabilisation(&[5; STABLE_GENERATIONS]), None);
+        assert_eq!(stabilisation(&[5; STABLE_GENERATIONS + 1]), Some(0));
+
+        // The longest period has to repeat four times
+        let period: Vec<i128> = (0..MAX_PERIOD as i128).collect();
+        let populations: Vec<i128> = period.iter().cycle().take(MAX_PERIOD * 5 - 1).copied().collect();
+        assert_eq!(stabilisation(&populations), None);
+        let populations: Vec<i128> = period.iter().cycle().take(MAX_PERIOD * 5).copied().collect();
+        assert_eq!(stabilisation(&populations), Some(0));
+    }
+
+    #[test]
+    fn does_not_stabilise_a_growing_population() {
+        let populations: Vec<i128> = (0..1000).collect();
+        assert_eq!(stabilisation(&populations), None);
+        // A period longer than the longest one that is looked for
+        let populations: Vec<i128> = (0..=MAX_PERIOD as i128).cycle().take(1000).collect();
+        assert_eq!(stabilisation(&populations), None);
+    }
+}
@@ -20,28 +20,75 @@ pub fn create_shader_program(display: &Display<WindowSurface>) -> Program {
     uniform sampler2D tex;
     uniform vec2 screensize;
     uniform vec2 boardsize;
+    uniform vec2 offset;
+    uniform float zoom;
+    // 0 for plain cells, 1 to colour live cells by age and 2 to also draw trails of dead cells
+    uniform int colouring;
+    uniform vec3 newborn;
+    uniform vec3 old;
+    uniform vec3 trail;
+    uniform bool heatmap;
+    uniform sampler2D heat;
+    uniform vec3 foreground;
+    uniform vec3 background;
+    uniform vec3 grid;
+    uniform vec3 outside;
+    uniform bool showgrid;
+    // Cells between major grid lines, 0 for none
+    uniform float majorgrid;
+    // Whether to highlight the axes through the origin, the center of the board
+    uniform bool axes;
+    uniform vec2 origin;
 
     void main() {
-        vec4 foreground = vec4(0.075,0.298,0.812, 1.0);
-        vec4 background = vec4(0.114,0.114,0.114, 1);
-        vec4 grid = background * 1.3;
-
-        vec2 pos = gl_FragCoord.xy / screensize;
-        pos.y = 1.0 - pos.y;
-        if(texture(tex, pos).r > 0){
-            color = foreground;
+        // The board position of the pixel, with the origin at the top left like the board
+        vec2 pixel = vec2(gl_FragCoord.x, screensize.y - gl_FragCoord.y);
+        vec2 cell = offset + pixel / zoom;
+        if (cell.x < 0 || cell.y < 0 || cell.x >= boardsize.x || cell.y >= boardsize.y) {
+            color = vec4(outside, 1.0);
+            return;
+        }
+
+        vec4 state = texture(tex, cell / boardsize);
+        if(state.r > 0){
+            color = vec4(colouring > 0 ? mix(newborn, old, state.g) : foreground, 1.0);
+        }
+        else if(colouring > 1 && state.b > 0){
+            color = vec4(mix(background, trail, state.b), 1.0);
         }
         else{
-            color = background;
+            color = vec4(background, 1.0);
+        }
+
+        // The heat goes from dark red through yellow to white, like the heatmap images
+        float h = heatmap ? texture(heat, cell / boardsize).r : 0.0;
+        if (h > 0) {
+            vec3 hot = clamp(h * 3.0 - vec3(0.0, 1.0, 2.0), 0.0, 1.0);
+            color = mix(color, vec4(hot, 1.0), 0.8);
         }
 
-        vec2 cell_size = screensize / boardsize;
+        // Lines are a pixel wide along the top and left of their cells, the lines between cells fade
+        // out as the cells get too small to tell apart and the major lines once those get too close
+        vec2 position = cell - origin;
+        if (showgrid) {
+            vec2 minor = fract(position) * zoom;
+            if (minor.x < 1 || minor.y < 1) {
+                color = mix(color, vec4(grid, 1.0), smoothstep(2.0, 6.0, zoom));
+            }
 
-        if (cell_size.x > 1 && cell_size.y > 1){
-            vec2 mod = mod(pos * screensize, cell_size);
+            if (majorgrid > 0) {
+                vec2 major = fract(position / majorgrid) * majorgrid * zoom;
+                if (major.x < 1 || major.y < 1) {
+                    vec3 majorcolour = mix(grid, foreground, 0.3);
+                    color = mix(color, vec4(majorcolour, 1.0), smoothstep(2.0, 6.0, zoom * majorgrid));
+                }
+            }
+        }
 
-            if (abs(mod.x) < 1 || abs(mod.y) < 1) {
-                color = grid;
+        if (axes) {
+            vec2 axis = abs(position * zoom);
+            if (axis.x < 1 || axis.y < 1) {
+                color = vec4(mix(grid, foreground, 0.7), 1.0);
             }
         }
     }
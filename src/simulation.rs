@@ -0,0 +1,147 @@
+use std::thread;
+
+use strum::{EnumCount, FromRepr};
+
+use crate::board::Board;
+use crate::packed::step_rows;
+use crate::rule::Rule;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub x: i64,
+    pub y: i64,
+    pub width: u64,
+    pub height: u64,
+}
+
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum Engine {
+    Grid,
+    HashLife,
+    Sparse,
+}
+
+impl Engine {
+    pub fn parse(name: &str) -> Result<Engine, String> {
+        (0..Engine::COUNT)
+            .filter_map(Engine::from_repr)
+            .find(|engine| engine.key() == name.trim().to_lowercase())
+            .ok_or(format!("Unknown engine '{}', expected one of grid, hashlife or sparse", name))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Engine::Grid => "grid",
+            Engine::HashLife => "hashlife",
+            Engine::Sparse => "sparse",
+        }
+    }
+}
+
+/// A simulation engine that can be stepped and queried independently of how it stores its cells
+pub trait Simulation {
+    /// Fails when the cells grow beyond the coordinates the engine can hold
+    fn step(&mut self, generations: u64) -> Result<(), String>;
+
+    fn generation(&self) -> i128;
+
+    fn population(&self) -> i128;
+
+    fn bounding_box(&self) -> Option<BoundingBox>;
+
+    fn set_cell(&mut self, x: i64, y: i64, value: bool);
+
+    fn is_cell_occupied(&self, x: i64, y: i64) -> bool;
+
+    fn live_cells(&self) -> Vec<(i64, i64)>;
+
+    /// Places a grid of cells centered on the given coordinate
+    fn set_cells(&mut self, cells: &[Vec<u8>], x: i64, y: i64) {
+        let top = y - (cells.len() / 2) as i64;
+        let left = x - (cells.first().map_or(0, |row| row.len()) / 2) as i64;
+
+        for (pattern_y, row) in cells.iter().enumerate() {
+            for (pattern_x, &cell) in row.iter().enumerate() {
+                self.set_cell(left + pattern_x as i64, top + pattern_y as i64, cell == 1);
+            }
+        }
+    }
+}
+
+// Boards smaller than this many words per thread are not worth spreading over threads
+const MIN_WORDS_PER_THREAD: usize = 4096;
+
+/// The simulation engine of the viewer, stepping a `Board` one generation at a time
+pub struct GridSimulation {
+    pub board: Board,
+    pub rule: Rule,
+    pub threads: usize,
+}
+
+impl GridSimulation {
+    pub fn new(board: Board, rule: Rule) -> Self {
+        GridSimulation { board, rule, threads: 1 }
+    }
+}
+
+impl Simulation for GridSimulation {
+    fn step(&mut self, generations: u64) -> Result<(), String> {
+        for _ in 0..generations {
+            self.board = perform_generation(&self.board, &self.rule, self.threads);
+        }
+        Ok(())
+    }
+
+    fn generation(&self) -> i128 {
+        self.board.generation()
+    }
+
+    fn population(&self) -> i128 {
+        self.board.population()
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.board.bounding_box()
+    }
+
+    fn set_cell(&mut self, x: i64, y: i64, value: bool) {
+        if let Some((x, y)) = self.board.resolve(x, y) {
+            self.board.set_cell(x, y, value);
+        }
+    }
+
+    fn is_cell_occupied(&self, x: i64, y: i64) -> bool {
+        match self.board.resolve(x, y) {
+            Some((x, y)) => self.board.is_cell_occupied(x, y),
+            None => false,
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        self.board.live_cells().map(|(x, y)| (x as i64, y as i64)).collect()
+    }
+}
+
+/// Computes the next generation of the board, splitting the rows in bands that are computed on
+/// up to `threads` threads. The result does not depend on the number of threads.
+pub fn perform_generation(board: &Board, rule: &Rule, threads: usize) -> Board {
+    let mut new_board = board.cleared();
+    let words_per_row = board.words_per_row();
+    let mut cells = vec![0u64; words_per_row * board.height()];
+
+    let threads = threads.min(cells.len() / MIN_WORDS_PER_THREAD).max(1);
+    if threads == 1 {
+        step_rows(board, rule, 0, &mut cells);
+    } else {
+        let rows_per_band = board.height().div_ceil(threads);
+        thread::scope(|scope| {
+            for (band, output) in cells.chunks_mut(rows_per_band * words_per_row).enumerate() {
+                scope.spawn(move || step_rows(board, rule, band * rows_per_band, output));
+            }
+        });
+    }
+
+    new_board.set_rows(cells);
+    new_board.set_generation(board.generation() + 1);
+    new_board
+}
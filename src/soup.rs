@@ -0,0 +1,207 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use strum::{EnumCount, FromRepr};
+
+/// Symmetries of a soup in the notation of apgsearch, C for rotations and D for rotations combined
+/// with reflections
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum Symmetry {
+    C1,
+    C2,
+    C4,
+    D2,
+    D4,
+    D8,
+}
+
+impl Symmetry {
+    pub fn parse(name: &str) -> Result<Symmetry, String> {
+        (0..Symmetry::COUNT)
+            .filter_map(Symmetry::from_repr)
+            .find(|symmetry| symmetry.key() == name.trim().to_lowercase())
+            .ok_or(format!("Unknown symmetry '{}', expected one of c1, c2, c4, d2, d4 or d8", name))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Symmetry::C1 => "c1",
+            Symmetry::C2 => "c2",
+            Symmetry::C4 => "c4",
+            Symmetry::D2 => "d2",
+            Symmetry::D4 => "d4",
+            Symmetry::D8 => "d8",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Symmetry::C1 => "C1",
+            Symmetry::C2 => "C2",
+            Symmetry::C4 => "C4",
+            Symmetry::D2 => "D2",
+            Symmetry::D4 => "D4",
+            Symmetry::D8 => "D8",
+        }
+    }
+
+    // Quarter turns and the diagonal reflection swap the axes, so they only fit square soups
+    pub fn needs_square(&self) -> bool {
+        matches!(*self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    // The cells a cell is mapped onto by the symmetry, including the cell itself
+    fn images(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (mirror_x, mirror_y) = (width - 1 - x, height - 1 - y);
+
+        match *self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (mirror_x, mirror_y)],
+            Symmetry::C4 => vec![(x, y), (mirror_y, x), (mirror_x, mirror_y), (y, mirror_x)],
+            Symmetry::D2 => vec![(x, y), (mirror_x, y)],
+            Symmetry::D4 => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y)],
+            Symmetry::D8 => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y),
+                                 (y, x), (mirror_y, x), (y, mirror_x), (mirror_y, mirror_x)],
+        }
+    }
+}
+
+/// A rectangle of random cells, the same seed always gives the same soup
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Soup {
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    // Chance of each cell to be alive, between 0 and 1
+    pub density: f64,
+    pub symmetry: Symmetry,
+}
+
+impl Soup {
+    pub fn new(seed: u64, width: usize, height: usize) -> Self {
+        Soup {
+            seed,
+            width,
+            height,
+            density: 0.5,
+            symmetry: Symmetry::C1,
+        }
+    }
+
+    pub fn grid(&self) -> Result<Vec<Vec<u8>>, String> {
+        if self.symmetry.needs_square() && self.width != self.height {
+            return Err(format!("Symmetry {} needs a square soup, not {}x{}", self.symmetry.name(),
+                               self.width, self.height));
+        }
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(format!("Invalid soup density {}, expected a value between 0 and 1", self.density));
+        }
+
+        // Cells are sampled from the raw numbers of ChaCha8, which rand_chacha keeps the same for a seed
+        // across versions, rather than through gen_bool whose sampling may change
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut grid: Vec<Vec<Option<u8>>> = vec![vec![None; self.width]; self.height];
+
+        // Every cell that isn't decided yet by the symmetry decides all of its images
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if grid[y][x].is_some() {
+                    continue;
+                }
+                let cell = (unit_interval(rng.next_u64()) < self.density) as u8;
+                for (image_x, image_y) in self.symmetry.images(x, y, self.width, self.height) {
+                    grid[image_y][image_x] = Some(cell);
+                }
+            }
+        }
+
+        Ok(grid.into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.unwrap_or(0)).collect())
+            .collect())
+    }
+}
+
+// Maps a random number onto [0, 1) using the 53 bits a float can hold exactly
+fn unit_interval(number: u64) -> f64 {
+    (number >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(symmetry: Symmetry, width: usize, height: usize) -> Vec<Vec<u8>> {
+        Soup { symmetry, ..Soup::new(7, width, height) }.grid().unwrap()
+    }
+
+    fn transformed(grid: &[Vec<u8>], transform: impl Fn(usize, usize) -> (usize, usize)) -> Vec<Vec<u8>> {
+        (0..grid.len())
+            .map(|y| (0..grid[y].len()).map(|x| {
+                let (x, y) = transform(x, y);
+                grid[y][x]
+            }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_grid_of_a_seed() {
+        assert_eq!(Soup::new(1, 8, 8).grid().unwrap(), vec![
+            vec![1, 1, 0, 1, 1, 0, 1, 1],
+            vec![0, 0, 1, 0, 1, 1, 1, 1],
+            vec![0, 0, 0, 1, 1, 1, 1, 1],
+            vec![1, 1, 0, 1, 1, 1, 0, 0],
+            vec![1, 0, 0, 0, 1, 0, 1, 0],
+            vec![0, 0, 0, 1, 1, 0, 1, 0],
+            vec![1, 1, 0, 0, 1, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 1, 0, 0],
+        ]);
+    }
+
+    #[test]
+    fn fills_soups_of_extreme_densities() {
+        let soup = Soup::new(1, 8, 8);
+        assert_eq!(Soup { density: 0.0, ..soup }.grid().unwrap(), vec![vec![0; 8]; 8]);
+        assert_eq!(Soup { density: 1.0, ..soup }.grid().unwrap(), vec![vec![1; 8]; 8]);
+    }
+
+    #[test]
+    fn generates_symmetric_soups() {
+        // Odd and even sizes, so soups with and without a center row and column
+        for (width, height) in [(16, 16), (15, 15), (16, 9)] {
+            let (last_x, last_y) = (width - 1, height - 1);
+            let half_turn = |x, y| (last_x - x, last_y - y);
+            let mirror = |x, y| (last_x - x, y);
+            let flip = |x, y| (x, last_y - y);
+
+            let c2 = grid(Symmetry::C2, width, height);
+            assert_eq!(transformed(&c2, half_turn), c2);
+            let d2 = grid(Symmetry::D2, width, height);
+            assert_eq!(transformed(&d2, mirror), d2);
+            let d4 = grid(Symmetry::D4, width, height);
+            assert_eq!(transformed(&d4, mirror), d4);
+            assert_eq!(transformed(&d4, flip), d4);
+
+            if width == height {
+                let quarter_turn = |x, y| (y, last_x - x);
+                let diagonal = |x, y| (y, x);
+
+                let c4 = grid(Symmetry::C4, width, height);
+                assert_eq!(transformed(&c4, quarter_turn), c4);
+                let d8 = grid(Symmetry::D8, width, height);
+                assert_eq!(transformed(&d8, quarter_turn), d8);
+                assert_eq!(transformed(&d8, mirror), d8);
+                assert_eq!(transformed(&d8, diagonal), d8);
+            }
+
+            // Without symmetry the soup isn't symmetric by chance
+            let c1 = grid(Symmetry::C1, width, height);
+            assert_ne!(transformed(&c1, half_turn), c1);
+            assert_ne!(transformed(&c1, mirror), c1);
+        }
+    }
+
+    #[test]
+    fn rejects_rectangles_for_square_symmetries() {
+        assert!(Soup { symmetry: Symmetry::C4, ..Soup::new(1, 16, 9) }.grid().is_err());
+        assert!(Soup { symmetry: Symmetry::D8, ..Soup::new(1, 16, 9) }.grid().is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use crate::packed::{count_masks, count_neighbours, matches};
+use crate::rule::Rule;
+use crate::simulation::{BoundingBox, Simulation};
+
+const TILE_SIZE: i64 = 64;
+
+// A tile of 64x64 cells, one word per row with the bit for column x at bit x
+type Tile = [u64; TILE_SIZE as usize];
+
+/// An unbounded plane that only stores the tiles that contain alive cells, so patterns can grow
+/// in any direction without running into an edge
+pub struct SparseBoard {
+    rule: Rule,
+    tiles: HashMap<(i64, i64), Tile>,
+    generation: i128,
+    population: i128,
+}
+
+impl SparseBoard {
+    /// Rules where cells are born without neighbours fill the whole plane and are not supported
+    pub fn new(rule: Rule) -> Result<Self, String> {
+        if rule.is_born(0) {
+            return Err(format!("An unbounded plane does not support rule {} with birth on 0 \
+                                neighbours", rule));
+        }
+
+        Ok(SparseBoard {
+            rule,
+            tiles: HashMap::new(),
+            generation: 1,
+            population: 0,
+        })
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn perform_generation(&mut self) {
+        let (birth, survival) = count_masks(&self.rule);
+
+        // Cells can only be born next to alive cells, so only the occupied tiles and the tiles
+        // around them can be alive in the next generation
+        let mut candidates: Vec<(i64, i64)> = Vec::new();
+        for &(tile_x, tile_y) in self.tiles.keys() {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    candidates.push((tile_x + dx, tile_y + dy));
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut tiles = HashMap::new();
+        let mut population = 0;
+
+        for (tile_x, tile_y) in candidates {
+            let tile = self.next_tile(tile_x, tile_y, &birth, &survival);
+            let tile_population: u32 = tile.iter().map(|row| row.count_ones()).sum();
+
+            if tile_population > 0 {
+                population += tile_population as i128;
+                tiles.insert((tile_x, tile_y), tile);
+            }
+        }
+
+        self.tiles = tiles;
+        self.population = population;
+        self.generation += 1;
+    }
+
+    fn next_tile(&self, tile_x: i64, tile_y: i64, birth: &[i8], survival: &[i8]) -> Tile {
+        let mut around: [[Option<&Tile>; 3]; 3] = [[None; 3]; 3];
+        for (dy, row) in around.iter_mut().enumerate() {
+            for (dx, tile) in row.iter_mut().enumerate() {
+                *tile = self.tiles.get(&(tile_x + dx as i64 - 1, tile_y + dy as i64 - 1));
+            }
+        }
+
+        let mut next = [0u64; TILE_SIZE as usize];
+        if around.iter().flatten().all(|tile| tile.is_none()) {
+            return next;
+        }
+
+        for (y, word) in next.iter_mut().enumerate() {
+            let y = y as i64;
+            let (above, above_west, above_east) = extended_row(&around, y - 1);
+            let (current, current_west, current_east) = extended_row(&around, y);
+            let (below, below_west, below_east) = extended_row(&around, y + 1);
+
+            let count = count_neighbours([
+                (above << 1) | above_west, above, (above >> 1) | (above_east << 63),
+                (current << 1) | current_west, (current >> 1) | (current_east << 63),
+                (below << 1) | below_west, below, (below >> 1) | (below_east << 63),
+            ]);
+
+            *word = (current & matches(count, survival)) | (!current & matches(count, birth));
+        }
+        next
+    }
+}
+
+// A row of the center tile, or of the tile above or below it for rows just outside of it, with
+// the cells just beyond its west and east edge
+fn extended_row(around: &[[Option<&Tile>; 3]; 3], y: i64) -> (u64, u64, u64) {
+    let (tile_row, y) = match y {
+        y if y < 0 => (0, TILE_SIZE - 1),
+        y if y >= TILE_SIZE => (2, 0),
+        y => (1, y),
+    };
+    let word_of = |tile: Option<&Tile>| tile.map_or(0, |tile| tile[y as usize]);
+    let tiles = &around[tile_row];
+
+    (word_of(tiles[1]), word_of(tiles[0]) >> 63, word_of(tiles[2]) & 1)
+}
+
+fn tile_position(x: i64, y: i64) -> ((i64, i64), usize, usize) {
+    let tile = (x.div_euclid(TILE_SIZE), y.div_euclid(TILE_SIZE));
+    (tile, x.rem_euclid(TILE_SIZE) as usize, y.rem_euclid(TILE_SIZE) as usize)
+}
+
+impl Simulation for SparseBoard {
+    fn step(&mut self, generations: u64) -> Result<(), String> {
+        for _ in 0..generations {
+            self.perform_generation();
+        }
+        Ok(())
+    }
+
+    fn generation(&self) -> i128 {
+        self.generation
+    }
+
+    fn population(&self) -> i128 {
+        self.population
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+
+        for (&(tile_x, tile_y), tile) in &self.tiles {
+            let top = tile.iter().position(|&row| row != 0).unwrap() as i64;
+            let bottom = tile.iter().rposition(|&row| row != 0).unwrap() as i64;
+            let columns = tile.iter().fold(0, |columns, &row| columns | row);
+            let left = columns.trailing_zeros() as i64;
+            let right = 63 - columns.leading_zeros() as i64;
+
+            let (left, top) = (tile_x * TILE_SIZE + left, tile_y * TILE_SIZE + top);
+            let (right, bottom) = (tile_x * TILE_SIZE + right, tile_y * TILE_SIZE + bottom);
+            bounds = Some(match bounds {
+                None => (left, top, right, bottom),
+                Some(b) => (b.0.min(left), b.1.min(top), b.2.max(right), b.3.max(bottom)),
+            });
+        }
+
+        let (left, top, right, bottom) = bounds?;
+        Some(BoundingBox {
+            x: left,
+            y: top,
+            width: (right - left + 1) as u64,
+            height: (bottom - top + 1) as u64,
+        })
+    }
+
+    fn set_cell(&mut self, x: i64, y: i64, value: bool) {
+        let (position, x, y) = tile_position(x, y);
+        let mask = 1u64 << x;
+
+        if value {
+            let tile = self.tiles.entry(position).or_insert([0; TILE_SIZE as usize]);
+            if tile[y] & mask == 0 {
+                tile[y] |= mask;
+                self.population += 1;
+            }
+        } else if let Some(tile) = self.tiles.get_mut(&position) {
+            if tile[y] & mask != 0 {
+                tile[y] &= !mask;
+                self.population -= 1;
+            }
+            if tile.iter().all(|&row| row == 0) {
+                self.tiles.remove(&position);
+            }
+        }
+    }
+
+    fn is_cell_occupied(&self, x: i64, y: i64) -> bool {
+        let (position, x, y) = tile_position(x, y);
+        self.tiles.get(&position).is_some_and(|tile| tile[y] & (1u64 << x) != 0)
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        for (&(tile_x, tile_y), tile) in &self.tiles {
+            for (y, &row) in tile.iter().enumerate() {
+                for x in (0..TILE_SIZE).filter(|&x| row & (1u64 << x) != 0) {
+                    cells.push((tile_x * TILE_SIZE + x, tile_y * TILE_SIZE + y as i64));
+                }
+            }
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::board::Board;
+    use crate::simulation::perform_generation;
+    use crate::topology::Topology;
+
+    const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    // Offset of the origin of the sparse board on a packed board with room for every test pattern
+    const OFFSET: i64 = 256;
+
+    fn sorted_cells(simulation: &SparseBoard) -> Vec<(i64, i64)> {
+        let mut cells = simulation.live_cells();
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    fn board_cells(board: &Board) -> Vec<(i64, i64)> {
+        board.live_cells().map(|(x, y)| (x as i64 - OFFSET, y as i64 - OFFSET)).collect()
+    }
+
+    #[test]
+    fn matches_the_packed_board_across_tile_edges() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // A soup around the corner of four tiles
+        let soup: Vec<(i64, i64)> = (0..256).filter(|_| rng.gen_bool(0.5))
+            .map(|index| (index % 16 - 8, index / 16 - 8))
+            .collect();
+
+        for rule in ["B3/S23", "B36/S23", "B2/S"] {
+            let rule = Rule::parse(rule).unwrap();
+            let mut sparse = SparseBoard::new(rule).unwrap();
+            let mut board = Board::with_size(2 * OFFSET as usize, 2 * OFFSET as usize);
+            board.topology = Topology::Plane;
+            for &(x, y) in &soup {
+                sparse.set_cell(x, y, true);
+                board.set_cell((x + OFFSET) as usize, (y + OFFSET) as usize, true);
+            }
+
+            for _ in 0..100 {
+                sparse.perform_generation();
+                board = perform_generation(&board, &rule, 1);
+                assert_eq!(sorted_cells(&sparse), board_cells(&board), "rule {} generation {}", rule,
+                           sparse.generation());
+                assert_eq!(sparse.population(), board.population());
+            }
+        }
+    }
+
+    #[test]
+    fn drops_tiles_that_empty_out() {
+        let mut sparse = SparseBoard::new(Rule::conway()).unwrap();
+        // A domino on the corner of four tiles dies right away
+        sparse.set_cell(-1, -1, true);
+        sparse.set_cell(0, 0, true);
+        assert_eq!(sparse.tile_count(), 2);
+        sparse.perform_generation();
+        assert_eq!(sparse.tile_count(), 0);
+
+        // A glider never covers more than the four tiles around a corner while it travels
+        for &(x, y) in &GLIDER {
+            sparse.set_cell(x, y, true);
+        }
+        for _ in 0..1000 {
+            sparse.perform_generation();
+            assert!(sparse.tile_count() <= 4);
+        }
+        assert_eq!(sparse.bounding_box().map(|bounding_box| (bounding_box.x, bounding_box.y)), Some((250, 250)));
+
+        for (x, y) in sparse.live_cells() {
+            sparse.set_cell(x, y, false);
+        }
+        assert_eq!(sparse.tile_count(), 0);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::Path;
+
+use image::Rgb;
+
+use crate::render::{parse_colour, Palette};
+
+// The built-in themes, in the same format as a themes file
+const BUILT_IN: &str = include_str!("../assets/themes.cfg");
+
+/// A named palette the viewer can switch to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+}
+
+impl Theme {
+    pub fn built_in() -> Vec<Theme> {
+        parse(BUILT_IN).unwrap()
+    }
+
+    /// The built-in themes followed by the themes of a file, a theme of the file with the name of
+    /// a built-in theme replaces it
+    pub fn load(path: &Path) -> Result<Vec<Theme>, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read '{}': {}", path.display(), error))?;
+        let loaded = parse(&contents).map_err(|error| format!("{} in '{}'", error, path.display()))?;
+
+        let mut themes = Theme::built_in();
+        for theme in loaded {
+            match themes.iter_mut().find(|existing| existing.name == theme.name) {
+                Some(existing) => *existing = theme,
+                None => themes.push(theme),
+            }
+        }
+        Ok(themes)
+    }
+
+    pub fn find(themes: &[Theme], name: &str) -> Result<usize, String> {
+        let name = name.trim().to_lowercase();
+        themes.iter().position(|theme| theme.name == name).ok_or_else(|| {
+            let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+            format!("Unknown theme '{}', expected one of {}", name, names.join(", "))
+        })
+    }
+}
+
+/// Parses themes written as a [name] line followed by lines of colour = #rrggbb, for the
+/// foreground, background, grid and the area outside the board. Lines starting with # are comments.
+pub fn parse(contents: &str) -> Result<Vec<Theme>, String> {
+    let mut themes = Vec::new();
+    // The theme being read, with the colours given so far
+    let mut current: Option<(String, [Option<Rgb<u8>>; 4])> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("Invalid theme line {} '{}'", number + 1, line);
+
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if let Some(theme) = current.take() {
+                themes.push(finish(theme)?);
+            }
+            current = Some((name.trim().to_lowercase(), [None; 4]));
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+        let (_, colours) = current.as_mut().ok_or_else(invalid)?;
+        let index = match key.trim() {
+            "foreground" => 0,
+            "background" => 1,
+            "grid" => 2,
+            "outside" => 3,
+            _ => return Err(invalid()),
+        };
+        colours[index] = Some(parse_colour(value)?);
+    }
+
+    if let Some(theme) = current {
+        themes.push(finish(theme)?);
+    }
+    Ok(themes)
+}
+
+fn finish((name, colours): (String, [Option<Rgb<u8>>; 4])) -> Result<Theme, String> {
+    let missing = |colour: &str| format!("Theme '{}' has no {} colour", name, colour);
+    let foreground = colours[0].ok_or_else(|| missing("foreground"))?;
+    let background = colours[1].ok_or_else(|| missing("background"))?;
+    let grid = colours[2].unwrap_or_else(|| default_grid(background));
+    let outside = colours[3].unwrap_or(Rgb([0, 0, 0]));
+
+    Ok(Theme {
+        name,
+        palette: Palette { foreground, background, grid, outside },
+    })
+}
+
+// The background brightened by 30% like the grid of the dark theme, light backgrounds are darkened
+fn default_grid(background: Rgb<u8>) -> Rgb<u8> {
+    let brightness = background.0.iter().map(|&channel| channel as u32).sum::<u32>() / 3;
+    let factor = if brightness > 160 { 0.85 } else { 1.3 };
+    Rgb(background.0.map(|channel| (channel as f64 * factor).min(255.0) as u8))
+}
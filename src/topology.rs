@@ -0,0 +1,165 @@
+use strum::{EnumCount, FromRepr};
+
+#[derive(FromRepr, Debug, PartialEq, EnumCount, Copy, Clone)]
+pub enum Topology {
+    Torus,
+    Plane,
+    KleinBottle,
+    CrossSurface,
+    Cylinder,
+}
+
+impl Topology {
+    pub fn parse(name: &str) -> Result<Topology, String> {
+        (0..Topology::COUNT)
+            .filter_map(Topology::from_repr)
+            .find(|topology| topology.key() == name.trim().to_lowercase())
+            .ok_or(format!("Unknown topology '{}', expected one of torus, plane, klein-bottle, \
+                            cross-surface or cylinder", name))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Topology::Torus => "torus",
+            Topology::Plane => "plane",
+            Topology::KleinBottle => "klein-bottle",
+            Topology::CrossSurface => "cross-surface",
+            Topology::Cylinder => "cylinder",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Topology::Torus => "Torus",
+            Topology::Plane => "Plane",
+            Topology::KleinBottle => "Klein bottle",
+            Topology::CrossSurface => "Cross-surface",
+            Topology::Cylinder => "Cylinder",
+        }
+    }
+
+    pub fn next(&self) -> Topology {
+        Topology::from_repr((*self as usize + 1) % Topology::COUNT).unwrap()
+    }
+
+    /// Maps a coordinate that may lie outside a board of the given size onto the cell it refers
+    /// to, or None when the coordinate falls off the edge of the board.
+    pub fn resolve(&self, x: i64, y: i64, width: usize, height: usize) -> Option<(usize, usize)> {
+        let width = width as i64;
+        let height = height as i64;
+
+        // Which axes wrap around, and whether crossing that edge mirrors the other axis
+        let (wrap_x, wrap_y, twist_x, twist_y) = match *self {
+            Topology::Torus => (true, true, false, false),
+            Topology::Plane => (false, false, false, false),
+            Topology::KleinBottle => (true, true, false, true),
+            Topology::CrossSurface => (true, true, true, true),
+            Topology::Cylinder => (true, false, false, false),
+        };
+
+        let mut x = x;
+        let mut y = y;
+
+        if x < 0 || x >= width {
+            if !wrap_x {
+                return None;
+            }
+            if twist_x && x.div_euclid(width) % 2 != 0 {
+                y = height - 1 - y;
+            }
+            x = x.rem_euclid(width);
+        }
+
+        if y < 0 || y >= height {
+            if !wrap_y {
+                return None;
+            }
+            if twist_y && y.div_euclid(height) % 2 != 0 {
+                x = width - 1 - x;
+            }
+            y = y.rem_euclid(height);
+        }
+
+        Some((x as usize, y as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A coordinate and the cell it should resolve to
+    type Expected = ((i64, i64), Option<(usize, usize)>);
+
+    // Resolves each coordinate on a board of 5x4 cells
+    fn assert_resolves(topology: Topology, expected: &[Expected]) {
+        for &((x, y), cell) in expected {
+            assert_eq!(topology.resolve(x, y, 5, 4), cell, "{} at ({}, {})", topology.name(), x, y);
+        }
+    }
+
+    #[test]
+    fn keeps_cells_on_the_board() {
+        for topology in (0..Topology::COUNT).filter_map(Topology::from_repr) {
+            assert_resolves(topology, &[((0, 0), Some((0, 0))), ((4, 3), Some((4, 3))), ((2, 1), Some((2, 1)))]);
+        }
+    }
+
+    #[test]
+    fn wraps_a_torus() {
+        assert_resolves(Topology::Torus, &[
+            ((-1, 0), Some((4, 0))),
+            ((5, 3), Some((0, 3))),
+            ((2, -1), Some((2, 3))),
+            ((2, 4), Some((2, 0))),
+            ((-1, -1), Some((4, 3))),
+            ((5, 4), Some((0, 0))),
+        ]);
+    }
+
+    #[test]
+    fn drops_cells_off_a_plane() {
+        assert_resolves(Topology::Plane, &[((-1, 0), None), ((5, 3), None), ((2, -1), None), ((2, 4), None),
+                                           ((-1, -1), None), ((5, 4), None)]);
+    }
+
+    #[test]
+    fn wraps_a_cylinder_around_its_sides_only() {
+        assert_resolves(Topology::Cylinder, &[
+            ((-1, 2), Some((4, 2))),
+            ((5, 0), Some((0, 0))),
+            ((2, -1), None),
+            ((2, 4), None),
+            ((-1, -1), None),
+            ((5, 4), None),
+        ]);
+    }
+
+    #[test]
+    fn mirrors_the_top_and_bottom_edges_of_a_klein_bottle() {
+        assert_resolves(Topology::KleinBottle, &[
+            ((-1, 1), Some((4, 1))),
+            ((5, 2), Some((0, 2))),
+            ((1, -1), Some((3, 3))),
+            ((0, 4), Some((4, 0))),
+            ((1, 8), Some((1, 0))),
+            ((-1, -1), Some((0, 3))),
+            ((5, 4), Some((4, 0))),
+            ((-1, 4), Some((0, 0))),
+        ]);
+    }
+
+    #[test]
+    fn mirrors_every_edge_of_a_cross_surface() {
+        assert_resolves(Topology::CrossSurface, &[
+            ((-1, 1), Some((4, 2))),
+            ((5, 0), Some((0, 3))),
+            ((1, -1), Some((3, 3))),
+            ((0, 4), Some((4, 0))),
+            ((10, 1), Some((0, 1))),
+            ((-1, -1), Some((0, 0))),
+            ((5, 4), Some((4, 3))),
+            ((5, -1), Some((4, 0))),
+        ]);
+    }
+}